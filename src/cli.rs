@@ -0,0 +1,141 @@
+// Command line arguments. Any option given here overrides the same field in the config file.
+// The state, key file and address override the profile used at startup.
+
+use std::path::PathBuf;
+
+use clap::{Parser, Subcommand};
+
+use crate::{config::{SpiderTuiConfig, Profile}, keymap::Preset};
+
+
+
+#[derive(Debug, Parser)]
+#[command(version, about = "A terminal interface for a Spider base")]
+pub struct Cli{
+    /// Config file to read
+    #[arg(short, long, global = true, default_value = "config.json")]
+    pub config: PathBuf,
+
+    /// Profile to connect with, instead of asking
+    #[arg(short, long, global = true)]
+    pub profile: Option<String>,
+
+    /// File holding this client's state
+    #[arg(long, global = true)]
+    pub state: Option<String>,
+
+    /// File holding the base's id
+    #[arg(long, global = true)]
+    pub keyfile: Option<String>,
+
+    /// Address of the base, such as localhost:1930
+    #[arg(long, global = true)]
+    pub base_addr: Option<String>,
+
+    /// File to write logs to
+    #[arg(long, global = true)]
+    pub log_path: Option<String>,
+
+    /// Log level or filter, such as "debug" or "spider_tui=trace". Takes precedence over RUST_LOG
+    #[arg(long, global = true)]
+    pub log_level: Option<String>,
+
+    /// Serve tokio-console on the configured address
+    #[arg(long, global = true)]
+    pub console: bool,
+
+    /// Show pages and datasets from a directory of fixtures instead of connecting to a base
+    #[arg(long, value_name = "DIR")]
+    pub mock: Option<PathBuf>,
+
+    /// Record the session to this file, for the replay command
+    #[arg(long, global = true)]
+    pub record: Option<String>,
+
+    /// Name of the theme to use
+    #[arg(long, global = true)]
+    pub theme: Option<String>,
+
+    /// Key binding preset
+    #[arg(long, global = true, value_enum)]
+    pub keys: Option<Preset>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command{
+    /// Connect to the base and show its pages (the default)
+    Run,
+    /// Create the client state and base key file
+    Init,
+    /// Print the saved relations with this client and its base
+    Info,
+    /// Replay a recorded session without connecting to a base
+    Replay{
+        /// The recorded session
+        file: PathBuf,
+        /// How many times faster than recorded, 0 replays without waiting
+        #[arg(long, default_value_t = 1.0)]
+        speed: f64,
+        /// Render into memory instead of the terminal, and print the final screen
+        #[arg(long)]
+        headless: bool,
+        /// Size of the headless screen, such as 80x24
+        #[arg(long, default_value = "80x24", value_parser = parse_size)]
+        size: (u16, u16),
+    },
+    /// Delete the client state
+    Reset{
+        /// Also delete the base key file
+        #[arg(long)]
+        keyfile: bool,
+        /// Do not ask for confirmation
+        #[arg(short, long)]
+        yes: bool,
+    },
+}
+
+impl Cli{
+    pub fn apply_overrides(&self, config: &mut SpiderTuiConfig){
+        if let Some(log_path) = &self.log_path {
+            config.log_path = log_path.clone();
+        }
+        if let Some(log_level) = &self.log_level {
+            config.logging.level = log_level.clone();
+        }
+        if self.console {
+            config.logging.console.enabled = true;
+        }
+        if let Some(record) = &self.record {
+            config.record_path = Some(record.clone());
+        }
+        if let Some(theme) = &self.theme {
+            config.theme.name = theme.clone();
+        }
+        if let Some(keys) = self.keys {
+            config.keybindings.preset = keys;
+        }
+    }
+
+    pub fn apply_profile_overrides(&self, profile: &mut Profile){
+        if let Some(state) = &self.state {
+            profile.state_data_path = state.clone();
+        }
+        if let Some(keyfile) = &self.keyfile {
+            profile.keyfile_path = keyfile.clone();
+        }
+        if let Some(base_addr) = &self.base_addr {
+            profile.addresses.insert(0, base_addr.clone());
+        }
+    }
+}
+
+// a screen size written as columns x rows
+fn parse_size(size: &str) -> Result<(u16, u16), String>{
+    let (width, height) = size.split_once('x').ok_or_else(|| String::from("expected a size such as 80x24"))?;
+    let width = width.trim().parse().map_err(|e| format!("invalid width: {}", e))?;
+    let height = height.trim().parse().map_err(|e| format!("invalid height: {}", e))?;
+    Ok((width, height))
+}
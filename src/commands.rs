@@ -0,0 +1,119 @@
+// Subcommands other than run, these work on the saved state without opening the interface.
+
+use std::{fs, io::{self, Write}, path::{Path, PathBuf}};
+
+use spider_client::{SpiderClient, SpiderId2048, Relation, Role, AddressStrategy};
+
+use crate::{config::Profile, error::StartupError};
+
+
+
+// create the client state and base key file, asking the user for the details
+pub fn init(profile: &Profile) -> Result<(), StartupError>{
+    let state_path = Path::new(&profile.state_data_path);
+    if state_path.exists() && !confirm(&format!("{} already exists, replace it?", state_path.display()))? {
+        println!("Kept the existing client state");
+        return Ok(());
+    }
+
+    let default_addr = profile.addresses.first().cloned().unwrap_or(String::from("localhost:1930"));
+    let addr = prompt("Base address", &default_addr)?;
+
+    // the base's id is exported from the base as a json file
+    let id = loop{
+        let source = prompt("File containing the base's id", &profile.keyfile_path)?;
+        let id = fs::read_to_string(&source).ok()
+            .and_then(|data| serde_json::from_str::<SpiderId2048>(&data).ok());
+        match id{
+            Some(id) => {
+                if source != profile.keyfile_path {
+                    let data = serde_json::to_string(&id).map_err(|e| StartupError::BadFile(PathBuf::from(&profile.keyfile_path), e))?;
+                    fs::write(&profile.keyfile_path, data)?;
+                    println!("Copied the base's id to {}", profile.keyfile_path);
+                }
+                break id;
+            },
+            None => println!("{} does not contain a base id, try again", source),
+        }
+    };
+
+    let mut client = SpiderClient::new();
+    client.set_state_path(state_path);
+    client.add_strat(AddressStrategy::Addr(addr));
+    client.set_host_relation(Relation { id, role: Role::Peer });
+    client.save();
+
+    println!("Saved the client state to {}", state_path.display());
+    println!("This client's id, for the base to approve:");
+    println!("{}", id_text(&client.self_relation().id, state_path)?);
+    Ok(())
+}
+
+// print the relations saved in the client state
+pub fn info(profile: &Profile) -> Result<(), StartupError>{
+    let state_path = Path::new(&profile.state_data_path);
+    if !state_path.exists() {
+        println!("No client state at {}, create one with the init command", state_path.display());
+        return Ok(());
+    }
+    let client = SpiderClient::from_file(state_path);
+    let relation = client.self_relation();
+    println!("State file: {}", state_path.display());
+    println!("Role: {:?}", relation.role);
+    println!("Id: {}", id_text(&relation.id, state_path)?);
+
+    match client.host_relation() {
+        Some(host) => {
+            println!("Host role: {:?}", host.role);
+            println!("Host id: {}", id_text(&host.id, state_path)?);
+        },
+        None => println!("Host: none"),
+    }
+    Ok(())
+}
+
+// delete the client state, and the key file if asked
+pub fn reset(profile: &Profile, keyfile: bool, yes: bool) -> Result<(), StartupError>{
+    let mut paths = vec![profile.state_data_path.clone()];
+    if keyfile {
+        paths.push(profile.keyfile_path.clone());
+    }
+    paths.retain(|path| Path::new(path).exists());
+    if paths.is_empty() {
+        println!("Nothing to reset");
+        return Ok(());
+    }
+    if !yes && !confirm(&format!("Delete {}?", paths.join(", ")))? {
+        return Ok(());
+    }
+    for path in paths{
+        fs::remove_file(&path)?;
+        println!("Deleted {}", path);
+    }
+    Ok(())
+}
+
+
+
+// an id from the client state, as the base shows it
+fn id_text(id: &SpiderId2048, state_path: &Path) -> Result<String, StartupError>{
+    serde_json::to_string(id).map_err(|e| StartupError::BadFile(state_path.to_path_buf(), e))
+}
+
+fn prompt(question: &str, default: &str) -> io::Result<String>{
+    print!("{} [{}]: ", question, default);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    let answer = answer.trim();
+    if answer.is_empty() {
+        Ok(default.to_string())
+    }else{
+        Ok(answer.to_string())
+    }
+}
+
+fn confirm(question: &str) -> io::Result<bool>{
+    let answer = prompt(question, "y/N")?;
+    Ok(matches!(answer.to_lowercase().as_str(), "y" | "yes"))
+}
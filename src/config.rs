@@ -1,8 +1,10 @@
 
-use std::{fs, path::Path};
+use std::{fs, io::ErrorKind, path::Path, collections::BTreeMap};
 
 use serde::{Serialize, Deserialize};
 
+use crate::{error::StartupError, identity::IdentityConfig, keymap::KeybindingsConfig, logging::LoggingConfig, model::processor::{page_list::PageListConfig, status::StatusBarConfig, traffic::TrafficConfig}, renderer::theme::ThemeConfig};
+
 
 
 
@@ -11,6 +13,8 @@ use serde::{Serialize, Deserialize};
 pub struct SpiderTuiConfig{
     #[serde(default = "default_log_path")]
     pub log_path: String,
+    #[serde(default)]
+    pub logging: LoggingConfig,
     #[serde(default = "default_state_data_path")]
     pub state_data_path: String,
 
@@ -20,21 +24,100 @@ pub struct SpiderTuiConfig{
     #[serde(default = "keyfile_path")]
     pub keyfile_path: String,
 
+    // named bases, used instead of the fields above when there are any
+    #[serde(default)]
+    pub profiles: BTreeMap<String, Profile>,
+    #[serde(default)]
+    pub default_profile: Option<String>,
+
+    #[serde(default)]
+    pub keybindings: KeybindingsConfig,
+
+    #[serde(default)]
+    pub theme: ThemeConfig,
+
+    #[serde(default)]
+    pub status_bar: StatusBarConfig,
+
+    #[serde(default)]
+    pub identity: IdentityConfig,
+
+    #[serde(default)]
+    pub page_list: PageListConfig,
+
+    #[serde(default)]
+    pub traffic: TrafficConfig,
+
+    // every update to the model is written here, to be replayed later
+    #[serde(default)]
+    pub record_path: Option<String>,
+
+    // the most frames drawn each second, 0 for no limit
+    #[serde(default = "default_frame_rate")]
+    pub frame_rate: u32,
 }
 
 
 impl SpiderTuiConfig {
-    pub fn from_file(path: &Path) -> Self {
+    // a missing file gives the default config
+    pub fn from_file(path: &Path) -> Result<Self, StartupError> {
         let data = match fs::read_to_string(&path){
             Ok(str) => str,
-            Err(_) => String::from("{}"),
+            Err(e) if e.kind() == ErrorKind::NotFound => String::from("{}"),
+            Err(e) => return Err(StartupError::ConfigRead(path.to_path_buf(), e)),
+        };
+        serde_json::from_str(&data).map_err(|e| StartupError::ConfigParse(path.to_path_buf(), e))
+    }
+
+    // all profiles, in order of name. Without any named profiles, the top level fields make the only profile
+    pub fn profiles(&self) -> Vec<Profile> {
+        if self.profiles.is_empty() {
+            return vec![Profile {
+                name: String::from(DEFAULT_PROFILE),
+                state_data_path: self.state_data_path.clone(),
+                keyfile_path: self.keyfile_path.clone(),
+                addresses: self.base_addr.clone().into_iter().collect(),
+            }];
+        }
+        self.profiles.iter().map(|(name, profile)| {
+            let mut profile = profile.clone();
+            profile.name = name.clone();
+            profile
+        }).collect()
+    }
+
+    // the profile to use without asking, if it is clear which one that is
+    pub fn select_profile(&self, name: Option<&str>) -> Result<Option<Profile>, StartupError> {
+        let profiles = self.profiles();
+        let name = match name.or(self.default_profile.as_deref()){
+            Some(name) => name,
+            None if profiles.len() == 1 => return Ok(profiles.into_iter().next()),
+            None => return Ok(None),
         };
-        // let data = fs::read_to_string(&path).expect(&format!("Failed to read config file: {:?}", path));
-		let config = serde_json::from_str(&data).expect("Failed to deserialize config");
-        config
+        match profiles.iter().find(|profile| profile.name == name){
+            Some(profile) => Ok(Some(profile.clone())),
+            None => {
+                let names = profiles.into_iter().map(|profile| profile.name).collect();
+                Err(StartupError::UnknownProfile(name.to_string(), names))
+            },
+        }
     }
 }
 
+// A base to connect to, with its own client state and key file
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct Profile{
+    #[serde(skip)]
+    pub name: String,
+    pub state_data_path: String,
+    pub keyfile_path: String,
+    // tried in order, after any saved in the client state
+    #[serde(default)]
+    pub addresses: Vec<String>,
+}
+
+pub const DEFAULT_PROFILE: &str = "default";
+
 
 
 
@@ -49,4 +132,8 @@ fn default_state_data_path() -> String {
 
 fn keyfile_path() -> String {
     "spider_keyfile.json".into()
+}
+
+fn default_frame_rate() -> u32 {
+    30
 }
\ No newline at end of file
@@ -0,0 +1,91 @@
+// tracks the connection to the base and schedules reconnection attempts
+
+use std::{future::{pending, Future}, pin::Pin, time::Duration};
+
+use spider_client::{message::Message, SpiderClient};
+use tokio::time::{timeout, Instant};
+use tracing::{info_span, Instrument};
+
+const INITIAL_DELAY: Duration = Duration::from_secs(1);
+const MAX_DELAY: Duration = Duration::from_secs(60);
+// how long a connection attempt may take before it is given up
+pub const CONNECT_TIMEOUT: Duration = Duration::from_secs(10);
+
+pub struct Reconnector {
+    connected: bool,
+    delay: Duration,
+    next_attempt: Instant,
+}
+
+impl Reconnector {
+    // when not already connected, the first attempt is due straight away
+    pub fn new(connected: bool) -> Self {
+        Self {
+            connected,
+            delay: INITIAL_DELAY,
+            next_attempt: Instant::now(),
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    // the connection dropped (or an attempt failed), schedule the next attempt
+    // and back off for the one after that.
+    pub fn connection_lost(&mut self) {
+        self.connected = false;
+        self.next_attempt = Instant::now() + self.delay;
+        self.delay = (self.delay * 2).min(MAX_DELAY);
+    }
+
+    // an attempt has succeeded, connected until the client says otherwise
+    pub fn connection_made(&mut self) {
+        self.connected = true;
+    }
+
+    // traffic was received, the connection is healthy so reset the backoff
+    pub fn connection_confirmed(&mut self) {
+        self.delay = INITIAL_DELAY;
+    }
+
+    pub fn attempt_due(&self) -> bool {
+        !self.connected && Instant::now() >= self.next_attempt
+    }
+
+    pub fn seconds_remaining(&self) -> u64 {
+        let remaining = self.next_attempt.saturating_duration_since(Instant::now());
+        // round up so that the countdown never shows 0 before the attempt
+        remaining.as_secs() + if remaining.subsec_nanos() > 0 { 1 } else { 0 }
+    }
+}
+
+// A connection attempt in progress. It holds the client until it is done, so that keys and
+// the model are not kept waiting meanwhile.
+pub struct ConnectAttempt(Pin<Box<dyn Future<Output = (SpiderClient, bool)>>>);
+
+impl ConnectAttempt {
+    pub fn start(mut client: SpiderClient) -> Self {
+        let attempt = async move {
+            let connected = timeout(CONNECT_TIMEOUT, client.connect()).await.is_ok();
+            (client, connected)
+        };
+        Self(Box::pin(attempt.instrument(info_span!("client_connect"))))
+    }
+}
+
+// the client and whether it connected, once the attempt is done. Never without an attempt
+pub async fn finish_attempt(attempt: &mut Option<ConnectAttempt>) -> (SpiderClient, bool) {
+    match attempt {
+        Some(ConnectAttempt(future)) => future.await,
+        None => pending().await,
+    }
+}
+
+// the next message from the base. Never while an attempt holds the client
+pub async fn recv_from(client: &mut Option<SpiderClient>) -> Option<Message> {
+    match client {
+        Some(client) => client.recv().await,
+        None => pending().await,
+    }
+}
@@ -0,0 +1,113 @@
+// Problems that prevent the interface from starting, each explains what to fix.
+
+use std::{fmt, io, path::PathBuf};
+
+
+
+#[derive(Debug)]
+pub enum StartupError{
+    ConfigRead(PathBuf, io::Error),
+    ConfigParse(PathBuf, serde_json::Error),
+    UnknownProfile(String, Vec<String>),
+    NoProfile(Vec<String>),
+    SetupCancelled(PathBuf),
+    KeyfileParse(PathBuf, serde_json::Error),
+    SessionRead(PathBuf, io::Error),
+    SessionParse(PathBuf, usize, serde_json::Error), // the line that could not be read
+    MockRead(PathBuf, io::Error),
+    MockParse(PathBuf, serde_json::Error),
+    BadFile(PathBuf, serde_json::Error), // json read from or written for a file
+    Terminal(io::Error),
+    Io(io::Error),
+}
+
+impl StartupError{
+    // the process exit code for this error
+    pub fn exit_code(&self) -> u8{
+        match self{
+            StartupError::Io(_) => 1,
+            StartupError::ConfigRead(_, _) => 2,
+            StartupError::ConfigParse(_, _) => 2,
+            StartupError::UnknownProfile(_, _) => 2,
+            StartupError::NoProfile(_) => 2,
+            StartupError::SetupCancelled(_) => 3,
+            StartupError::KeyfileParse(_, _) => 3,
+            StartupError::SessionRead(_, _) => 2,
+            StartupError::SessionParse(_, _, _) => 2,
+            StartupError::MockRead(_, _) => 2,
+            StartupError::MockParse(_, _) => 2,
+            StartupError::BadFile(_, _) => 2,
+            StartupError::Terminal(_) => 4,
+        }
+    }
+}
+
+impl fmt::Display for StartupError{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self{
+            StartupError::ConfigRead(path, e) => {
+                write!(f, "Could not read the config file {}: {}\nCheck that it is readable, or choose another with --config.", path.display(), e)
+            },
+            StartupError::ConfigParse(path, e) => {
+                write!(f, "The config file {} is not valid: {}\nFix the json at that line, or remove the file to use the defaults.", path.display(), e)
+            },
+            StartupError::UnknownProfile(name, names) => {
+                write!(f, "There is no profile named {:?}.\nThe configured profiles are: {}", name, names.join(", "))
+            },
+            StartupError::NoProfile(names) => {
+                write!(f, "Several profiles are configured, choose one with --profile.\nThe configured profiles are: {}", names.join(", "))
+            },
+            StartupError::SetupCancelled(path) => {
+                write!(f, "Setup was cancelled before a base was chosen.\nRun again to finish it, or export the base's id to {}.", path.display())
+            },
+            StartupError::KeyfileParse(path, e) => {
+                write!(f, "The base's key file {} does not contain a valid id: {}\nExport the base's id to that file again, or run the init command.", path.display(), e)
+            },
+            StartupError::SessionRead(path, e) => {
+                write!(f, "Could not read the recorded session {}: {}", path.display(), e)
+            },
+            StartupError::SessionParse(path, line, e) => {
+                write!(f, "The recorded session {} is not valid at line {}: {}\nIt may have been recorded by another version.", path.display(), line, e)
+            },
+            StartupError::MockRead(path, e) => {
+                write!(f, "Could not read the mock base fixture {}: {}", path.display(), e)
+            },
+            StartupError::MockParse(path, e) => {
+                write!(f, "The mock base fixture {} is not valid: {}\nFix the json, or move the file out of the fixture directory.", path.display(), e)
+            },
+            StartupError::BadFile(path, e) => {
+                write!(f, "Could not convert the json for {}: {}\nCheck the file, or delete it with the reset command and set up again.", path.display(), e)
+            },
+            StartupError::Terminal(e) => {
+                write!(f, "Could not set up the terminal: {}\nThe interface needs to be run in an interactive terminal.", e)
+            },
+            StartupError::Io(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for StartupError{
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self{
+            StartupError::ConfigRead(_, e) => Some(e),
+            StartupError::ConfigParse(_, e) => Some(e),
+            StartupError::UnknownProfile(_, _) => None,
+            StartupError::NoProfile(_) => None,
+            StartupError::SetupCancelled(_) => None,
+            StartupError::KeyfileParse(_, e) => Some(e),
+            StartupError::SessionRead(_, e) => Some(e),
+            StartupError::SessionParse(_, _, e) => Some(e),
+            StartupError::MockRead(_, e) => Some(e),
+            StartupError::MockParse(_, e) => Some(e),
+            StartupError::BadFile(_, e) => Some(e),
+            StartupError::Terminal(e) => Some(e),
+            StartupError::Io(e) => Some(e),
+        }
+    }
+}
+
+impl From<io::Error> for StartupError{
+    fn from(e: io::Error) -> Self {
+        StartupError::Io(e)
+    }
+}
@@ -0,0 +1,83 @@
+// How this terminal describes itself to the router.
+//
+// Values may contain placeholders that are filled in when announced:
+// {hostname}, {user}, {profile}, {terminal_size} and {version}.
+
+use std::{collections::BTreeMap, env, fs};
+
+use serde::{Serialize, Deserialize};
+
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct IdentityConfig{
+    #[serde(default = "default_name")]
+    pub name: String,
+    // extra properties, such as "host": "{hostname}" or "supports_grid": "true"
+    #[serde(default)]
+    pub properties: BTreeMap<String, String>,
+}
+
+impl Default for IdentityConfig{
+    fn default() -> Self {
+        Self {
+            name: default_name(),
+            properties: BTreeMap::new(),
+        }
+    }
+}
+
+// the values available to templates
+pub struct TemplateVars{
+    vars: Vec<(&'static str, String)>,
+}
+
+impl TemplateVars{
+    pub fn new(profile: &str) -> Self{
+        let terminal_size = match crossterm::terminal::size(){
+            Ok((width, height)) => format!("{}x{}", width, height),
+            Err(_) => String::from("unknown"),
+        };
+        Self {
+            vars: vec![
+                ("hostname", hostname()),
+                ("user", user()),
+                ("profile", profile.to_string()),
+                ("terminal_size", terminal_size),
+                ("version", String::from(env!("CARGO_PKG_VERSION"))),
+            ],
+        }
+    }
+
+    // replace each known {placeholder}, anything else is left as written
+    pub fn expand(&self, template: &str) -> String{
+        let mut result = template.to_string();
+        for (key, value) in self.vars.iter(){
+            result = result.replace(&format!("{{{}}}", key), value);
+        }
+        result
+    }
+}
+
+fn hostname() -> String{
+    if let Ok(name) = env::var("HOSTNAME") {
+        return name;
+    }
+    match fs::read_to_string("/etc/hostname"){
+        Ok(name) if !name.trim().is_empty() => name.trim().to_string(),
+        _ => env::var("COMPUTERNAME").unwrap_or(String::from("unknown")),
+    }
+}
+
+fn user() -> String{
+    env::var("USER")
+        .or_else(|_| env::var("USERNAME"))
+        .unwrap_or(String::from("unknown"))
+}
+
+
+
+// Defaults
+fn default_name() -> String {
+    "TUI".into()
+}
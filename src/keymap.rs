@@ -0,0 +1,441 @@
+// Maps key presses to actions, depending on what the user is doing.
+//
+// Bindings are written as a key optionally prefixed by modifiers, such as "q", "ctrl+w" or
+// "shift+tab". A chord is several keys separated by spaces, such as "g g" or "ctrl+x ctrl+c".
+
+use std::{collections::HashMap, fmt};
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Action{
+    Quit,
+    SwitchProfile,
+    OpenMenu,
+    ToggleDebug,
+    Submit,
+    // page list
+    Filter,
+    CycleSort,
+    // traffic monitor
+    TogglePause,
+    Export,
+    // tabs
+    NextTab,
+    PrevTab,
+    CloseTab,
+    // split panes
+    SplitSideBySide,
+    SplitStacked,
+    Unsplit,
+    FocusNextPane,
+    GrowPane,
+    ShrinkPane,
+    NavigateUp,
+    NavigateDown,
+    NavigateLeft,
+    NavigateRight,
+    ScrollPageUp,
+    ScrollPageDown,
+    ScrollTop,
+    ScrollBottom,
+    // text editing
+    CursorLeft,
+    CursorRight,
+    CursorHome,
+    CursorEnd,
+    DeleteBack,
+    DeleteForward,
+    DeleteWord,
+    KillLine,
+}
+
+// What the user is doing, each mode has its own bindings
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyMode{
+    List, // choosing a page
+    Page, // navigating a page
+    Edit, // a text entry is selected. Unbound characters are typed into it
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize, clap::ValueEnum)]
+#[serde(rename_all = "snake_case")]
+pub enum Preset{
+    #[default]
+    Default,
+    Vim,
+    Emacs,
+}
+
+// The keybindings section of the config. Bindings listed for an action replace
+// the preset's bindings for that action in that mode.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct KeybindingsConfig{
+    #[serde(default)]
+    pub preset: Preset,
+    #[serde(default)]
+    pub list: HashMap<Action, Vec<String>>,
+    #[serde(default)]
+    pub page: HashMap<Action, Vec<String>>,
+    #[serde(default)]
+    pub edit: HashMap<Action, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct KeyPress{
+    code: KeyCode,
+    modifiers: KeyModifiers,
+}
+
+impl KeyPress{
+    fn new(code: KeyCode, mut modifiers: KeyModifiers) -> Self{
+        // the case of a character already says whether shift was held, as does backtab
+        if let KeyCode::Char(_) | KeyCode::BackTab = code {
+            modifiers.remove(KeyModifiers::SHIFT);
+        }
+        Self { code, modifiers }
+    }
+
+    // a key that would type a character
+    fn is_printable(&self) -> bool{
+        matches!(self.code, KeyCode::Char(_)) && !self.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+    }
+
+    pub fn parse(s: &str) -> Option<Self>{
+        let mut modifiers = KeyModifiers::NONE;
+        let mut parts: Vec<&str> = s.split('+').collect();
+        let key = parts.pop()?;
+        for part in parts{
+            match part.to_lowercase().as_str(){
+                "ctrl" | "control" => modifiers.insert(KeyModifiers::CONTROL),
+                "alt" | "meta" => modifiers.insert(KeyModifiers::ALT),
+                "shift" => modifiers.insert(KeyModifiers::SHIFT),
+                _ => return None,
+            }
+        }
+        let code = match key.to_lowercase().as_str(){
+            "enter" | "return" => KeyCode::Enter,
+            "esc" | "escape" => KeyCode::Esc,
+            "backspace" => KeyCode::Backspace,
+            "delete" | "del" => KeyCode::Delete,
+            "insert" | "ins" => KeyCode::Insert,
+            "tab" => KeyCode::Tab,
+            "backtab" => KeyCode::BackTab,
+            "up" => KeyCode::Up,
+            "down" => KeyCode::Down,
+            "left" => KeyCode::Left,
+            "right" => KeyCode::Right,
+            "home" => KeyCode::Home,
+            "end" => KeyCode::End,
+            "pageup" | "pgup" => KeyCode::PageUp,
+            "pagedown" | "pgdn" => KeyCode::PageDown,
+            "space" => KeyCode::Char(' '),
+            "plus" => KeyCode::Char('+'),
+            lower => {
+                let mut chars = key.chars();
+                match (chars.next(), chars.next()){
+                    (Some(ch), None) => KeyCode::Char(ch),
+                    _ => {
+                        let n = lower.strip_prefix('f')?.parse().ok()?;
+                        KeyCode::F(n)
+                    },
+                }
+            },
+        };
+        Some(Self::new(code, modifiers))
+    }
+}
+
+// written the way bindings are configured
+impl fmt::Display for KeyPress{
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.modifiers.contains(KeyModifiers::CONTROL) {
+            write!(f, "ctrl+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::ALT) {
+            write!(f, "alt+")?;
+        }
+        if self.modifiers.contains(KeyModifiers::SHIFT) {
+            write!(f, "shift+")?;
+        }
+        match self.code{
+            KeyCode::Enter => write!(f, "enter"),
+            KeyCode::Esc => write!(f, "esc"),
+            KeyCode::Backspace => write!(f, "backspace"),
+            KeyCode::Delete => write!(f, "delete"),
+            KeyCode::Insert => write!(f, "insert"),
+            KeyCode::Tab => write!(f, "tab"),
+            KeyCode::BackTab => write!(f, "backtab"),
+            KeyCode::Up => write!(f, "up"),
+            KeyCode::Down => write!(f, "down"),
+            KeyCode::Left => write!(f, "left"),
+            KeyCode::Right => write!(f, "right"),
+            KeyCode::Home => write!(f, "home"),
+            KeyCode::End => write!(f, "end"),
+            KeyCode::PageUp => write!(f, "pageup"),
+            KeyCode::PageDown => write!(f, "pagedown"),
+            KeyCode::Char(' ') => write!(f, "space"),
+            KeyCode::Char('+') => write!(f, "plus"),
+            KeyCode::Char(ch) => write!(f, "{}", ch),
+            KeyCode::F(n) => write!(f, "f{}", n),
+            code => write!(f, "{:?}", code),
+        }
+    }
+}
+
+impl From<KeyEvent> for KeyPress{
+    fn from(key: KeyEvent) -> Self {
+        Self::new(key.code, key.modifiers)
+    }
+}
+
+fn parse_chord(s: &str) -> Option<Vec<KeyPress>>{
+    let chord: Option<Vec<KeyPress>> = s.split_whitespace().map(KeyPress::parse).collect();
+    chord.filter(|chord| !chord.is_empty())
+}
+
+pub enum KeyResult{
+    Action(Action),
+    Pending, // part of a chord
+    Unbound,
+}
+
+type Bindings = HashMap<Vec<KeyPress>, Action>;
+
+pub struct Keymap{
+    list: Bindings,
+    page: Bindings,
+    edit: Bindings,
+    pending: Vec<KeyPress>,
+}
+
+impl Keymap{
+    pub fn from_config(config: &KeybindingsConfig) -> Self{
+        let (list, page, edit) = preset_bindings(config.preset);
+        let list = build_bindings(list, &config.list);
+        let page = build_bindings(page, &config.page);
+        let mut edit = build_bindings(edit, &config.edit);
+
+        // page bindings still work while editing, unless they would prevent typing
+        for (chord, action) in page.iter(){
+            if !chord[0].is_printable() && !edit.keys().any(|c| c[0] == chord[0]) {
+                edit.insert(chord.clone(), *action);
+            }
+        }
+
+        Self {
+            list,
+            page,
+            edit,
+            pending: Vec::new(),
+        }
+    }
+
+    pub fn resolve(&mut self, mode: KeyMode, key: KeyEvent) -> KeyResult{
+        let bindings = match mode{
+            KeyMode::List => &self.list,
+            KeyMode::Page => &self.page,
+            KeyMode::Edit => &self.edit,
+        };
+        self.pending.push(key.into());
+        if let Some(action) = bindings.get(&self.pending) {
+            self.pending.clear();
+            return KeyResult::Action(*action);
+        }
+        if bindings.keys().any(|chord| chord.len() > self.pending.len() && chord.starts_with(&self.pending)) {
+            return KeyResult::Pending;
+        }
+        // not part of any chord, try the latest key on its own
+        let retry = self.pending.len() > 1;
+        self.pending.clear();
+        if retry {
+            return self.resolve(mode, key);
+        }
+        KeyResult::Unbound
+    }
+
+    // the shortest key binding for an action, for showing to the user
+    pub fn key_for(&self, mode: KeyMode, action: Action) -> Option<String>{
+        let bindings = match mode{
+            KeyMode::List => &self.list,
+            KeyMode::Page => &self.page,
+            KeyMode::Edit => &self.edit,
+        };
+        let chord = bindings.iter()
+            .filter(|(_, bound)| **bound == action)
+            .map(|(chord, _)| chord)
+            .min_by_key(|chord| (chord.len(), chord.iter().map(|key| key.to_string()).collect::<Vec<_>>()))?;
+        let keys: Vec<String> = chord.iter().map(|key| key.to_string()).collect();
+        Some(keys.join(" "))
+    }
+}
+
+fn build_bindings(preset: Vec<(Action, &str)>, overrides: &HashMap<Action, Vec<String>>) -> Bindings{
+    let mut bindings = Bindings::new();
+    for (action, key) in preset{
+        if overrides.contains_key(&action) {
+            continue;
+        }
+        if let Some(chord) = parse_chord(key) {
+            bindings.insert(chord, action);
+        }
+    }
+    for (action, keys) in overrides{
+        for key in keys{
+            match parse_chord(key){
+                Some(chord) => {
+                    bindings.insert(chord, *action);
+                },
+                None => warn!("Ignoring unrecognised key binding {:?} for {:?}", key, action),
+            }
+        }
+    }
+    bindings
+}
+
+fn preset_bindings(preset: Preset) -> (Vec<(Action, &'static str)>, Vec<(Action, &'static str)>, Vec<(Action, &'static str)>){
+    let mut list = vec![
+        (Action::Quit, "q"),
+        (Action::SwitchProfile, "p"),
+        (Action::OpenMenu, "esc"),
+        (Action::Submit, "enter"),
+        (Action::NavigateUp, "up"),
+        (Action::NavigateDown, "down"),
+        (Action::NavigateLeft, "left"),
+        (Action::NavigateRight, "right"),
+        (Action::Filter, "/"),
+        (Action::CycleSort, "tab"),
+        (Action::TogglePause, "space"),
+        (Action::Export, "ctrl+s"),
+        (Action::NextTab, "ctrl+tab"),
+        (Action::NextTab, "alt+right"),
+        (Action::PrevTab, "ctrl+backtab"),
+        (Action::PrevTab, "alt+left"),
+    ];
+    let mut page = vec![
+        (Action::OpenMenu, "esc"),
+        (Action::Submit, "enter"),
+        (Action::NavigateUp, "up"),
+        (Action::NavigateDown, "down"),
+        (Action::NavigateLeft, "left"),
+        (Action::NavigateRight, "right"),
+        (Action::ScrollPageUp, "pageup"),
+        (Action::ScrollPageDown, "pagedown"),
+        (Action::ScrollTop, "home"),
+        (Action::ScrollBottom, "end"),
+        (Action::ToggleDebug, "f12"),
+        (Action::NextTab, "ctrl+tab"),
+        (Action::NextTab, "alt+right"),
+        (Action::PrevTab, "ctrl+backtab"),
+        (Action::PrevTab, "alt+left"),
+        (Action::CloseTab, "ctrl+w"),
+        (Action::SplitSideBySide, "alt+|"),
+        (Action::SplitStacked, "alt+-"),
+        (Action::Unsplit, "alt+c"),
+        (Action::FocusNextPane, "alt+o"),
+        (Action::GrowPane, "alt+."),
+        (Action::ShrinkPane, "alt+,"),
+    ];
+    let mut edit = vec![
+        (Action::CursorLeft, "left"),
+        (Action::CursorRight, "right"),
+        (Action::CursorHome, "home"),
+        (Action::CursorEnd, "end"),
+        (Action::DeleteBack, "backspace"),
+        (Action::DeleteForward, "delete"),
+        (Action::DeleteWord, "ctrl+w"),
+        (Action::KillLine, "ctrl+u"),
+    ];
+    match preset{
+        Preset::Default => {},
+        Preset::Vim => {
+            list.extend([
+                (Action::NavigateUp, "k"),
+                (Action::NavigateDown, "j"),
+                (Action::NavigateLeft, "h"),
+                (Action::Submit, "l"),
+            ]);
+            page.extend([
+                (Action::NavigateUp, "k"),
+                (Action::NavigateDown, "j"),
+                (Action::NavigateLeft, "h"),
+                (Action::NavigateRight, "l"),
+                (Action::ScrollPageUp, "ctrl+b"),
+                (Action::ScrollPageDown, "ctrl+f"),
+                (Action::ScrollTop, "g g"),
+                (Action::ScrollBottom, "G"),
+                (Action::NextTab, "g t"),
+                (Action::PrevTab, "g T"),
+            ]);
+        },
+        Preset::Emacs => {
+            list.extend([
+                (Action::Quit, "ctrl+x ctrl+c"),
+                (Action::OpenMenu, "ctrl+g"),
+                (Action::NavigateUp, "ctrl+p"),
+                (Action::NavigateDown, "ctrl+n"),
+                (Action::NavigateLeft, "ctrl+b"),
+                (Action::NavigateRight, "ctrl+f"),
+            ]);
+            page.extend([
+                (Action::OpenMenu, "ctrl+g"),
+                (Action::NavigateUp, "ctrl+p"),
+                (Action::NavigateDown, "ctrl+n"),
+                (Action::NavigateLeft, "ctrl+b"),
+                (Action::NavigateRight, "ctrl+f"),
+                (Action::ScrollPageUp, "alt+v"),
+                (Action::ScrollPageDown, "ctrl+v"),
+                (Action::ScrollTop, "alt+<"),
+                (Action::ScrollBottom, "alt+>"),
+                (Action::CloseTab, "ctrl+x k"),
+                (Action::SplitSideBySide, "ctrl+x 3"),
+                (Action::SplitStacked, "ctrl+x 2"),
+                (Action::Unsplit, "ctrl+x 1"),
+                (Action::FocusNextPane, "ctrl+x o"),
+                (Action::GrowPane, "ctrl+x }"),
+                (Action::ShrinkPane, "ctrl+x {"),
+            ]);
+            edit.extend([
+                (Action::CursorLeft, "ctrl+b"),
+                (Action::CursorRight, "ctrl+f"),
+                (Action::CursorHome, "ctrl+a"),
+                (Action::CursorEnd, "ctrl+e"),
+                (Action::DeleteForward, "ctrl+d"),
+                (Action::DeleteBack, "ctrl+h"),
+                (Action::DeleteWord, "alt+backspace"),
+            ]);
+        },
+    }
+    (list, page, edit)
+}
+
+#[cfg(test)]
+mod tests{
+    use super::*;
+
+    fn resolve(keymap: &mut Keymap, mode: KeyMode, key: &str) -> Option<Action>{
+        let key = KeyPress::parse(key).expect("a valid key");
+        match keymap.resolve(mode, KeyEvent::new(key.code, key.modifiers)){
+            KeyResult::Action(action) => Some(action),
+            _ => None,
+        }
+    }
+
+    // the page list is filtered after /, so letters bound in the list are typed into the filter
+    #[test]
+    fn list_filter_starts_with_slash(){
+        for preset in [Preset::Default, Preset::Vim, Preset::Emacs]{
+            let config = KeybindingsConfig { preset, ..KeybindingsConfig::default() };
+            let mut keymap = Keymap::from_config(&config);
+            assert_eq!(resolve(&mut keymap, KeyMode::List, "/"), Some(Action::Filter), "{:?}", preset);
+            for key in ["q", "p", "space", "j", "k", "h", "l"]{
+                assert_eq!(resolve(&mut keymap, KeyMode::Edit, key), None, "{:?} {}", preset, key);
+            }
+        }
+    }
+}
@@ -0,0 +1,148 @@
+// Diagnostics are written to a log file, since the terminal is taken by the interface.
+//
+// The level uses the same syntax as RUST_LOG, such as "info" or "spider_tui=debug,warn".
+// RUST_LOG takes precedence over the config when it is set.
+
+use std::{net::SocketAddr, path::Path, time::Duration};
+
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+use tracing_appender::{non_blocking::WorkerGuard, rolling::{RollingFileAppender, Rotation}};
+use tracing_subscriber::{EnvFilter, prelude::*};
+
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct LoggingConfig{
+    #[serde(default = "default_level")]
+    pub level: String,
+    #[serde(default)]
+    pub rotation: LogRotation,
+    #[serde(default)]
+    pub console: ConsoleConfig,
+}
+
+impl Default for LoggingConfig{
+    fn default() -> Self {
+        Self {
+            level: default_level(),
+            rotation: LogRotation::default(),
+            console: ConsoleConfig::default(),
+        }
+    }
+}
+
+// How often to start a new log file. Rotated files have the date appended to their name.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogRotation{
+    #[default]
+    Never,
+    Minutely,
+    Hourly,
+    Daily,
+}
+
+impl From<LogRotation> for Rotation{
+    fn from(rotation: LogRotation) -> Self {
+        match rotation{
+            LogRotation::Never => Rotation::NEVER,
+            LogRotation::Minutely => Rotation::MINUTELY,
+            LogRotation::Hourly => Rotation::HOURLY,
+            LogRotation::Daily => Rotation::DAILY,
+        }
+    }
+}
+
+// tokio-console server, for inspecting the runtime's tasks
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ConsoleConfig{
+    #[serde(default)]
+    pub enabled: bool,
+    #[serde(default = "default_console_addr")]
+    pub addr: String,
+}
+
+impl Default for ConsoleConfig{
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            addr: default_console_addr(),
+        }
+    }
+}
+
+// Set up the global subscriber. Logs are flushed when the guard is dropped, so keep it until exit.
+// A level given on the command line takes precedence over RUST_LOG.
+pub fn init(log_path: &str, config: &LoggingConfig, cli_level: Option<&str>) -> WorkerGuard{
+    let path = Path::new(log_path);
+    let directory = match path.parent(){
+        Some(parent) if !parent.as_os_str().is_empty() => parent,
+        _ => Path::new("."),
+    };
+    let file_name = path.file_name().unwrap_or(path.as_os_str());
+    let appender = RollingFileAppender::new(config.rotation.into(), directory, file_name);
+    let (writer, guard) = tracing_appender::non_blocking(appender);
+
+    let env_filter = match cli_level{
+        Some(_) => None,
+        None => EnvFilter::try_from_default_env().ok(),
+    };
+    let level = cli_level.unwrap_or(&config.level);
+    let mut bad_level = false;
+    let filter = match env_filter{
+        Some(filter) => filter,
+        None => EnvFilter::try_new(level).unwrap_or_else(|_| {
+            bad_level = true;
+            EnvFilter::new(default_level())
+        }),
+    };
+    let file_layer = tracing_subscriber::fmt::layer()
+        .with_ansi(false)
+        .with_writer(writer)
+        .with_filter(filter);
+
+    let mut bad_addr = false;
+    let console_layer = if config.console.enabled {
+        match config.console.addr.parse::<SocketAddr>(){
+            Ok(addr) => {
+                let layer = console_subscriber::ConsoleLayer::builder()
+                    .retention(Duration::from_secs(60))
+                    .server_addr(addr)
+                    .spawn();
+                Some(layer)
+            },
+            Err(_) => {
+                bad_addr = true;
+                None
+            },
+        }
+    }else{
+        None
+    };
+
+    tracing_subscriber::registry()
+        .with(console_layer)
+        .with(file_layer)
+        .init();
+
+    // these can only be reported once the subscriber exists
+    if bad_level {
+        warn!("Invalid log level {:?}, using {:?}", level, default_level());
+    }
+    if bad_addr {
+        warn!("Invalid console address {:?}, console disabled", config.console.addr);
+    }
+    guard
+}
+
+
+
+// Defaults
+fn default_level() -> String {
+    "info".into()
+}
+
+fn default_console_addr() -> String {
+    "127.0.0.1:6670".into()
+}
@@ -2,15 +2,29 @@ mod renderer;
 mod config;
 mod model;
 mod event_stream;
+mod connection;
+mod keymap;
+mod identity;
+mod logging;
+mod cli;
+mod commands;
+mod error;
+mod wizard;
+mod picker;
+mod replay;
+mod mock;
+#[cfg(test)]
+mod test_util;
 
-// use console_subscriber;
+use model::{Model, ModelExit, session::SessionNamer, update::{ModelUpdate, ConnectionState, TrafficEntry, TrafficDirection}};
+use crate::{config::{SpiderTuiConfig, Profile}, connection::{finish_attempt, recv_from, ConnectAttempt, Reconnector}, cli::{Cli, Command}, error::StartupError};
 
-use model::{Model, update::ModelUpdate};
-use crate::config::SpiderTuiConfig;
+use std::{path::{Path, PathBuf}, time::Duration, process::ExitCode};
 
-use std::{io, env, path::{Path, PathBuf}, time::Duration};
+use clap::Parser;
 
-use tokio::select;
+use crossterm::event::Event;
+use tokio::{select, sync::mpsc::{error::SendError, Receiver}, time::interval};
 
 use spider_client::{
     SpiderClient,
@@ -27,42 +41,142 @@ use spider_client::{
     AddressStrategy
 };
 
-use tracing::{info, debug};
-use tracing_appender::rolling::{Rotation, RollingFileAppender};
+use tracing::{info, debug, error, trace, instrument};
 
 #[tokio::main]
-async fn main() -> Result<(), io::Error> {
-    console_subscriber::ConsoleLayer::builder()
-        .retention(Duration::from_secs(60))
-        .server_addr(([127, 0, 0, 1], 6670))
-        .init();
-
-    // command line arguments: <filename>
-    // filename is name of config file, defaults to config.json
-    let mut args = env::args().skip(1);
-    let path_str = args.next().unwrap_or("config.json".to_string());
-    let config_path = Path::new(&path_str);
-
-    let config = SpiderTuiConfig::from_file(config_path);
-    let log_path = config.log_path.clone();
-    
+async fn main() -> ExitCode {
+    match start().await{
+        Ok(()) => ExitCode::SUCCESS,
+        Err(e) => {
+            error!("{}", e);
+            eprintln!("{}", e);
+            ExitCode::from(e.exit_code())
+        },
+    }
+}
+
+async fn start() -> Result<(), StartupError> {
+    let cli = Cli::parse();
+    let mut config = SpiderTuiConfig::from_file(&cli.config)?;
+    cli.apply_overrides(&mut config);
+
     // Setup tracing
-    // let file_appender = RollingFileAppender::new(Rotation::NEVER, "", log_path);
-    // let (non_blocking, _guard) = tracing_appender::non_blocking(file_appender);
-    // tracing_subscriber::fmt()
-    //     .pretty().with_ansi(false)
-    //     .with_writer(non_blocking)
-    //     .init();
+    let _log_guard = logging::init(&config.log_path, &config.logging, cli.log_level.as_deref());
     info!("Starting!");
 
-    let client_path = PathBuf::from(config.state_data_path);
+    let command = cli.command.clone().unwrap_or(Command::Run);
+    if let Command::Run = command {
+        return run(config, &cli).await;
+    }
+    if let Command::Replay { file, speed, headless, size } = &command {
+        let size = if *headless { Some(*size) } else { None };
+        return replay::run(config, file, *speed, size).await;
+    }
+
+    // the other commands can not ask which profile to use
+    let mut profile = match config.select_profile(cli.profile.as_deref())?{
+        Some(profile) => profile,
+        None => {
+            let names = config.profiles().into_iter().map(|profile| profile.name).collect();
+            return Err(StartupError::NoProfile(names));
+        },
+    };
+    cli.apply_profile_overrides(&mut profile);
+    match command{
+        Command::Run => unreachable!("handled above"),
+        Command::Replay { .. } => unreachable!("handled above"),
+        Command::Init => commands::init(&profile),
+        Command::Info => commands::info(&profile),
+        Command::Reset { keyfile, yes } => commands::reset(&profile, keyfile, yes),
+    }
+}
+
+// Connect to a base and show its pages, until the user quits.
+// Switching profile tears down the client and model and starts them again for the new base.
+// With --mock, fixture files stand in for the base instead.
+async fn run(config: SpiderTuiConfig, cli: &Cli) -> Result<(), StartupError> {
+    if let Some(dir) = &cli.mock {
+        return mock::run(config, dir).await;
+    }
+    let theme = renderer::theme::Theme::from_config(&config.theme);
+    renderer::tui::install_panic_hook();
+    let mut events = event_stream::get_event_stream();
+
+    let profiles = config.profiles();
+    let mut next = config.select_profile(cli.profile.as_deref())?;
+    if let Some(profile) = &mut next {
+        cli.apply_profile_overrides(profile);
+    }
+    let mut current: Option<Profile> = None;
+    let mut sessions = SessionNamer::default();
+
+    loop {
+        let mut renderer = renderer::tui::TUI::new(theme.clone()).map_err(StartupError::Terminal)?;
+
+        let profile = match next.take(){
+            Some(profile) => profile,
+            None => {
+                match picker::run(&mut renderer, &profiles, current.as_ref(), &config, &mut events).await{
+                    Some(profile) => profile,
+                    None => match current.take(){
+                        Some(profile) => profile, // stay with the current base
+                        None => return Ok(()),
+                    },
+                }
+            },
+        };
+        info!("Using profile {}", profile.name);
+
+        let mut client = load_client(&profile)?;
+
+        // without a base to connect to, ask the user for one
+        let mut connected = false;
+        if !client.has_host_relation() {
+            match wizard::run(&mut client, &profile, &config, &mut renderer, &mut events).await?{
+                Some(wizard_connected) => connected = wizard_connected,
+                // switching to a profile that is not set up yet, go back to choosing one
+                None if current.is_some() => {
+                    info!("Setup of profile {} cancelled", profile.name);
+                    continue;
+                },
+                None => return Err(StartupError::SetupCancelled(PathBuf::from(&profile.keyfile_path))),
+            }
+        }
+        // without a connection from the setup, the first attempt is made once the model is showing
+        // each start is recorded to its own file
+        let mut model_config = config.clone();
+        model_config.record_path = config.record_path.as_ref()
+            .map(|path| sessions.next(Path::new(path)).to_string_lossy().to_string());
+        let model = Model::start(renderer, client.self_relation(), &model_config, &profile).await;
+
+        // connect client and keyboard inputs to model, connect model outputs to base
+        let exit = splice_client_keyboard_model(client, connected, model, &mut events).await;
+        current = Some(profile);
+        match exit{
+            ModelExit::Quit => return Ok(()),
+            ModelExit::SwitchProfile => info!("Switching profile"),
+        }
+    }
+}
+
+fn load_client(profile: &Profile) -> Result<SpiderClient, StartupError> {
+    let client_path = PathBuf::from(&profile.state_data_path);
     let mut client = if client_path.exists(){
-        SpiderClient::from_file(&client_path)
+        let mut client = SpiderClient::from_file(&client_path);
+        // configured addresses replace the saved ones, so that they do not pile up run after run
+        if !profile.addresses.is_empty(){
+            client.clear_strats();
+            for addr in profile.addresses.iter(){
+                client.add_strat(AddressStrategy::Addr(addr.clone()));
+            }
+            client.save();
+        }
+        client
     }else{
         let mut client = SpiderClient::new();
         client.set_state_path(&client_path);
-        if let Some(addr) = config.base_addr{
-            client.add_strat(AddressStrategy::Addr(addr));
+        for addr in profile.addresses.iter(){
+            client.add_strat(AddressStrategy::Addr(addr.clone()));
         }
         client.add_strat(AddressStrategy::Addr(String::from("localhost:1930")));
         client.save();
@@ -70,39 +184,30 @@ async fn main() -> Result<(), io::Error> {
     };
     
     if !client.has_host_relation(){
-        let path = PathBuf::from(&config.keyfile_path);        
+        let path = PathBuf::from(&profile.keyfile_path);        
 
         let data = match std::fs::read_to_string(&path){
             Ok(str) => str,
-            Err(_) => String::from("[]"),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(client), // the setup wizard will ask for it
+            Err(e) => return Err(StartupError::Io(e)),
         };
-		let id:SpiderId2048 = serde_json::from_str(&data).expect("Failed to deserialize spiderid");
+        let id: SpiderId2048 = serde_json::from_str(&data).map_err(|e| StartupError::KeyfileParse(path, e))?;
         let host = Relation { id, role: Role::Peer };
         client.set_host_relation(host);
         client.save();
     }
-
-    client.connect().await;
-
-    let renderer = renderer::tui::TUI::new();
-    let model = Model::start(renderer, client.self_relation()).await;
-
-
-    
-
-
-    // connect client and keyboard inputs to model, connect model outputs to base
-    splice_client_keyboard_model(client, model).await;
-
-
-    Ok(())
+    Ok(client)
 }
 
 
 
-async fn splice_client_keyboard_model(mut client: SpiderClient, mut model: Model){
+async fn splice_client_keyboard_model(client: SpiderClient, connected: bool, mut model: Model, events: &mut Receiver<Event>) -> ModelExit{
 
-    let mut events = event_stream::get_event_stream();
+    let mut reconnector = Reconnector::new(connected);
+    let mut tick = interval(Duration::from_secs(1));
+    // the client is held by the attempt while one is in progress
+    let mut client = Some(client);
+    let mut attempt: Option<ConnectAttempt> = None;
     loop {
 
         select! {
@@ -122,24 +227,70 @@ async fn splice_client_keyboard_model(mut client: SpiderClient, mut model: Model
 
             }
             // client messages to model
-            from_client = client.recv() => {
+            from_client = recv_from(&mut client), if reconnector.is_connected() => {
                 match from_client{
                     Some(from_client) => {
-                        // println!("from client: {:?}", from_client);
-                        let update = message_to_update(from_client);
-                        if let Some(update) = update {
-                            model.send(update).await;
+                        reconnector.connection_confirmed();
+                        if deliver_to_model(&mut model, from_client).await.is_err() {
+                            break; // the model has quit
+                        }
+                    },
+                    None => {
+                        info!("Lost connection to base");
+                        reconnector.connection_lost();
+                        let state = ConnectionState::Reconnecting(reconnector.seconds_remaining());
+                        if model.send(ModelUpdate::ConnectionState(state)).await.is_err() {
+                            break; // the model has quit
                         }
                     },
-                    None => todo!("need to update the model about the client's disconnection and attempt a reconnection"),
+                }
+            }
+            // countdown to and start reconnection attempts
+            _ = tick.tick(), if !reconnector.is_connected() && attempt.is_none() => {
+                if reconnector.attempt_due() {
+                    if let Some(client) = client.take() {
+                        info!("Attempting to connect to base");
+                        attempt = Some(ConnectAttempt::start(client));
+                    }
+                    if model.send(ModelUpdate::ConnectionState(ConnectionState::Connecting)).await.is_err() {
+                        break; // the model has quit
+                    }
+                }else{
+                    let state = ConnectionState::Reconnecting(reconnector.seconds_remaining());
+                    if model.send(ModelUpdate::ConnectionState(state)).await.is_err() {
+                        break; // the model has quit
+                    }
+                }
+            }
+            // a connection attempt is done, keys and model messages were handled meanwhile
+            (attempted, succeeded) = finish_attempt(&mut attempt) => {
+                attempt = None;
+                client = Some(attempted);
+                let state = if succeeded {
+                    reconnector.connection_made();
+                    // the model will resubscribe and reannounce itself
+                    ConnectionState::Connected
+                }else{
+                    info!("Connection attempt timed out");
+                    reconnector.connection_lost();
+                    ConnectionState::Reconnecting(reconnector.seconds_remaining())
+                };
+                if model.send(ModelUpdate::ConnectionState(state)).await.is_err() {
+                    break; // the model has quit
                 }
             }
             // model messages to client
             from_model = model.recv() => {
                 match from_model{
                     Some(from_model) => {
-                        // println!("Message from model: {:?}", from_model);
-                        client.send(from_model).await
+                        match client.as_mut(){
+                            Some(client) if reconnector.is_connected() => {
+                                if deliver_to_base(client, &mut model, from_model).await.is_err() {
+                                    break; // the model has quit
+                                }
+                            },
+                            _ => debug!("Dropped message to base while disconnected"),
+                        }
                     },
                     None => break, // model has quit
                 }
@@ -147,10 +298,29 @@ async fn splice_client_keyboard_model(mut client: SpiderClient, mut model: Model
         }
     }
 
+    model.finish().await
 }
 
 
+#[instrument(level = "debug", skip_all)]
+async fn deliver_to_model(model: &mut Model, msg: Message) -> Result<(), SendError<ModelUpdate>>{
+    trace!(?msg, "Received from base");
+    model.send(ModelUpdate::Traffic(TrafficEntry::new(TrafficDirection::Inbound, &msg))).await?;
+    if let Some(update) = message_to_update(msg) {
+        model.send(update).await?;
+    }
+    Ok(())
+}
+
+#[instrument(level = "debug", skip_all)]
+async fn deliver_to_base(client: &mut SpiderClient, model: &mut Model, msg: Message) -> Result<(), SendError<ModelUpdate>>{
+    trace!(?msg, "Sending to base");
+    model.send(ModelUpdate::Traffic(TrafficEntry::new(TrafficDirection::Outbound, &msg))).await?;
+    client.send(msg).await;
+    Ok(())
+}
 
+#[instrument(level = "trace", skip_all)]
 fn message_to_update(msg: Message) -> Option<ModelUpdate> {
     match msg {
         Message::Ui(ui) => {
@@ -163,14 +333,15 @@ fn message_to_update(msg: Message) -> Option<ModelUpdate> {
                 UiMessage::Dataset(path, dataset) => Some(ModelUpdate::UpdateDataset(path, dataset)),
                 UiMessage::InputFor(_, _, _, _) => None,
 
-                UiMessage::SetPage(_) => None,
-                UiMessage::ClearPage => None,
-                UiMessage::UpdateElements(_) => None,
+                UiMessage::SetPage(page) => Some(ModelUpdate::SetPage(page)),
+                // these are for the page this client serves
+                UiMessage::ClearPage => Some(ModelUpdate::ClearPage),
+                UiMessage::UpdateElements(updates) => Some(ModelUpdate::UpdateElements(updates)),
                 UiMessage::Input(_, _, _) => None,
             }
         },
-        Message::Dataset(_) => None,
-        Message::Router (_) => None,
+        Message::Dataset(msg) => Some(ModelUpdate::Dataset(msg)),
+        Message::Router(msg) => Some(ModelUpdate::Router(msg)),
     }
 }
 
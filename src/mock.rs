@@ -0,0 +1,258 @@
+// A stand-in for a base, serving pages and datasets from fixture files, for working on page
+// layouts without a network. The fixture directory holds:
+//   pages/*.json     one serialized UiPage each, such as those in an exported traffic file
+//   datasets/*.json  {"path": AbsoluteDatasetPath, "data": [DatasetData, ...]}
+//   script.json      optional dataset updates over time:
+//                    {"period_ms": 5000, "updates": [{"at_ms": 1000, "path": ..., "data": [...]}]}
+//                    the script starts again every period, if there is one
+// Input to the pages is shown as a notice instead of being sent anywhere.
+
+use std::{collections::HashMap, fs, path::Path};
+
+use crossterm::event::Event;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use spider_client::{
+    SpiderClient,
+    message::{AbsoluteDatasetPath, DatasetData, Message, UiMessage, UiPage},
+};
+use tokio::{select, sync::mpsc::Receiver, time::{sleep_until, Duration, Instant}};
+use tracing::{info, debug, trace};
+
+use crate::{
+    config::{SpiderTuiConfig, Profile},
+    deliver_to_model,
+    error::StartupError,
+    event_stream,
+    model::{Model, ModelExit, update::{ModelUpdate, TrafficDirection, TrafficEntry}},
+    renderer::{theme::Theme, tui::{self, TUI}},
+};
+
+
+
+#[derive(Debug, Clone, Deserialize)]
+struct DatasetFixture {
+    path: AbsoluteDatasetPath,
+    #[serde(default)]
+    data: Vec<DatasetData>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+struct ScriptedUpdate {
+    at_ms: u64, // since the script started
+    path: AbsoluteDatasetPath,
+    #[serde(default)]
+    data: Vec<DatasetData>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+struct Script {
+    #[serde(default)]
+    period_ms: Option<u64>,
+    #[serde(default)]
+    updates: Vec<ScriptedUpdate>,
+}
+
+pub struct MockBase {
+    pages: Vec<UiPage>,
+    datasets: HashMap<AbsoluteDatasetPath, Vec<DatasetData>>,
+    script: Script,
+}
+
+impl MockBase {
+    pub fn load(dir: &Path) -> Result<Self, StartupError> {
+        let pages = read_fixtures(&dir.join("pages"))?;
+        let datasets = read_fixtures::<DatasetFixture>(&dir.join("datasets"))?
+            .into_iter()
+            .map(|fixture| (fixture.path, fixture.data))
+            .collect();
+        let script_path = dir.join("script.json");
+        let mut script: Script = if script_path.exists() {
+            read_fixture(&script_path)?
+        }else{
+            Script::default()
+        };
+        script.updates.sort_by_key(|update| update.at_ms);
+        Ok(Self {
+            pages,
+            datasets,
+            script,
+        })
+    }
+
+    // what the base would say back
+    fn respond(&self, msg: &Message) -> Vec<Message> {
+        match msg {
+            Message::Ui(UiMessage::Subscribe) => {
+                let mut replies = vec![Message::Ui(UiMessage::Pages(self.pages.clone()))];
+                for (path, data) in self.datasets.iter() {
+                    replies.push(Message::Ui(UiMessage::Dataset(path.clone(), data.clone())));
+                }
+                replies
+            },
+            Message::Ui(UiMessage::GetPage(id)) => {
+                self.pages.iter()
+                    .filter(|page| page.id() == id)
+                    .map(|page| Message::Ui(UiMessage::Page(page.clone())))
+                    .collect()
+            },
+            _ => Vec::new(),
+        }
+    }
+
+    // input to a page, written out for the user
+    fn describe_input(&self, msg: &Message) -> Option<String> {
+        match msg {
+            Message::Ui(UiMessage::InputFor(page_id, element_id, _, input)) => {
+                let page = self.pages.iter()
+                    .find(|page| page.id() == page_id)
+                    .map(|page| page.name().to_string())
+                    .unwrap_or_else(|| String::from("unknown page"));
+                Some(format!("Input for {} {}: {:?}", page, element_id, input))
+            },
+            _ => None,
+        }
+    }
+}
+
+// each json file in the directory, in order of name. A missing directory has none
+fn read_fixtures<T: DeserializeOwned>(dir: &Path) -> Result<Vec<T>, StartupError> {
+    let entries = match fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(StartupError::MockRead(dir.to_path_buf(), e)),
+    };
+    let mut paths = Vec::new();
+    for entry in entries {
+        let path = entry.map_err(|e| StartupError::MockRead(dir.to_path_buf(), e))?.path();
+        if path.extension().map_or(false, |ext| ext == "json") {
+            paths.push(path);
+        }
+    }
+    paths.sort();
+    paths.iter().map(|path| read_fixture(path)).collect()
+}
+
+fn read_fixture<T: DeserializeOwned>(path: &Path) -> Result<T, StartupError> {
+    let data = fs::read_to_string(path).map_err(|e| StartupError::MockRead(path.to_path_buf(), e))?;
+    serde_json::from_str(&data).map_err(|e| StartupError::MockParse(path.to_path_buf(), e))
+}
+
+// Show the fixtures until the user quits
+pub async fn run(config: SpiderTuiConfig, dir: &Path) -> Result<(), StartupError> {
+    let base = MockBase::load(dir)?;
+    info!("Serving {} mock pages from {:?}", base.pages.len(), dir);
+
+    // kept apart from the real profiles, so that their recent pages and tabs are not mixed up
+    let profile = Profile {
+        name: String::from("mock"),
+        state_data_path: config.state_data_path.clone(),
+        keyfile_path: config.keyfile_path.clone(),
+        addresses: vec![format!("mock:{}", dir.display())],
+    };
+    let relation = SpiderClient::new().self_relation();
+    let theme = Theme::from_config(&config.theme);
+
+    tui::install_panic_hook();
+    let mut events = event_stream::get_event_stream();
+    let renderer = TUI::new(theme).map_err(StartupError::Terminal)?;
+    let model = Model::start(renderer, relation, &config, &profile).await;
+    let exit = splice_mock_keyboard_model(base, model, &mut events).await;
+    if let ModelExit::SwitchProfile = exit {
+        info!("There are no other profiles to switch to with a mock base");
+    }
+    Ok(())
+}
+
+async fn splice_mock_keyboard_model(mut base: MockBase, mut model: Model, events: &mut Receiver<Event>) -> ModelExit {
+    let mut script_start = Instant::now();
+    let mut next_update = 0;
+    'serve: loop {
+        // a copy, as the base's datasets change when it is due
+        let scripted = base.script.updates.get(next_update).cloned();
+        let due = script_start + Duration::from_millis(scripted.as_ref().map_or(0, |update| update.at_ms));
+
+        select! {
+            // keypresses to model
+            event = events.recv() => {
+                match event {
+                    Some(event) => {
+                        if model.send(ModelUpdate::Event(event)).await.is_err() {
+                            debug!("Failed to send to model");
+                            break;
+                        }
+                    },
+                    None => break, // inputs have failed, quit
+                }
+            }
+            // model messages to the mock base
+            from_model = model.recv() => {
+                match from_model {
+                    Some(msg) => {
+                        trace!(?msg, "Sending to mock base");
+                        if model.send(ModelUpdate::Traffic(TrafficEntry::new(TrafficDirection::Outbound, &msg))).await.is_err() {
+                            break; // the model has quit
+                        }
+                        if let Some(text) = base.describe_input(&msg) {
+                            info!("{}", text);
+                            if model.send(ModelUpdate::Notice(text)).await.is_err() {
+                                break; // the model has quit
+                            }
+                        }
+                        for reply in base.respond(&msg) {
+                            if deliver_to_model(&mut model, reply).await.is_err() {
+                                break 'serve; // the model has quit
+                            }
+                        }
+                    },
+                    None => break, // model has quit
+                }
+            }
+            // scripted dataset updates
+            _ = sleep_until(due), if scripted.is_some() => {
+                if let Some(update) = scripted {
+                    let msg = Message::Ui(UiMessage::Dataset(update.path.clone(), update.data.clone()));
+                    base.datasets.insert(update.path, update.data);
+                    if deliver_to_model(&mut model, msg).await.is_err() {
+                        break; // the model has quit
+                    }
+                }
+                next_update += 1;
+                if next_update == base.script.updates.len() {
+                    if let Some(period) = base.script.period_ms {
+                        script_start += Duration::from_millis(period.max(1));
+                        next_update = 0;
+                    }
+                }
+            }
+        }
+    }
+
+    model.finish().await
+}
+
+#[cfg(test)]
+mod tests {
+    use spider_client::message::{UiElementKind, UiInput};
+
+    use crate::test_util::page;
+
+    use super::*;
+
+    #[test]
+    fn answers_like_a_base() {
+        let alpha = page("Alpha", UiElementKind::Rows, vec![]).get_page().clone();
+        let base = MockBase {
+            pages: vec![alpha.clone()],
+            datasets: HashMap::new(),
+            script: Script::default(),
+        };
+
+        let replies = base.respond(&Message::Ui(UiMessage::Subscribe));
+        assert!(matches!(replies.as_slice(), [Message::Ui(UiMessage::Pages(pages))] if pages.len() == 1));
+
+        let input = Message::Ui(UiMessage::InputFor(alpha.id().clone(), String::from("go"), vec![], UiInput::Click));
+        assert_eq!(base.describe_input(&input), Some(String::from("Input for Alpha go: Click")));
+        assert!(base.respond(&input).is_empty());
+    }
+}
@@ -1,19 +1,28 @@
 
 pub mod update;
+pub mod session;
 
 use spider_client::{message::Message, SelfRelation, Relation};
 use update::ModelUpdate;
+use session::SessionHeader;
 
 use std::thread::JoinHandle;
-use tokio::sync::mpsc::{channel, Receiver, Sender, error::SendError};
+use tokio::{sync::mpsc::{channel, Receiver, Sender, error::SendError}, task::spawn_blocking};
 
 pub(crate) mod processor;
 use processor::ModelProcessor;
 
-use crate::renderer::Renderer;
+use crate::{renderer::Renderer, config::{SpiderTuiConfig, Profile}};
+
+// Why the model stopped
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelExit{
+	Quit,
+	SwitchProfile,
+}
 
 pub struct Model{
-	_handle: JoinHandle<Result<(), std::io::Error>>,
+	handle: JoinHandle<Result<ModelExit, std::io::Error>>,
 	
 	//pipes in/out
 	model_tx: Sender<ModelUpdate>,
@@ -25,16 +34,33 @@ pub struct Model{
 
 impl Model{
 	// take piped inputs to modify rendered model
-	pub async fn start<R: Renderer>(renderer: R, relation: Relation) -> Self{
+	pub async fn start<R: Renderer>(renderer: R, relation: Relation, config: &SpiderTuiConfig, profile: &Profile) -> Self{
 
 		let (model_tx, mod_rx) = channel(50);
         let (mod_tx, model_rx) = channel(50);
 
-		let processor = ModelProcessor::new(mod_rx, mod_tx, renderer, relation).await;
+		let processor = ModelProcessor::new(mod_rx, mod_tx, renderer, relation, config, profile).await;
+		let thread = processor.start();
+
+		Self{
+			handle: thread,
+			model_tx,
+			model_rx,
+		}
+	}
+
+	// drive the model from a recorded session, starting where it started
+	pub async fn replay<R: Renderer>(renderer: R, relation: Relation, config: &SpiderTuiConfig, profile: &Profile, header: &SessionHeader) -> Self{
+
+		let (model_tx, mod_rx) = channel(50);
+		let (mod_tx, model_rx) = channel(50);
+
+		let mut processor = ModelProcessor::new(mod_rx, mod_tx, renderer, relation, config, profile).await;
+		processor.restore_session(header);
 		let thread = processor.start();
 
 		Self{
-			_handle: thread,
+			handle: thread,
 			model_tx,
 			model_rx,
 		}
@@ -48,5 +74,16 @@ impl Model{
 		self.model_tx.send(message).await
 	}
 
+	// stop the model if it is still running, and wait for it to shut down the renderer
+	pub async fn finish(self) -> ModelExit{
+		let Self { handle, model_tx, model_rx } = self;
+		drop(model_tx);
+		drop(model_rx);
+		match spawn_blocking(move || handle.join()).await{
+			Ok(Ok(Ok(exit))) => exit,
+			_ => ModelExit::Quit, // the model failed
+		}
+	}
+
 	
 }
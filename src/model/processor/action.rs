@@ -0,0 +1,204 @@
+use crate::{keymap::{Action, KeyMode}, renderer::Renderer, model::ModelExit};
+
+use super::{ModelProcessor, ModelView, PageState, page_state::SelectDirection, line_edit::EditOp, split::SplitDirection};
+
+
+
+impl<R: Renderer> ModelProcessor<R>{
+	// which set of key bindings applies
+	pub(crate) fn key_mode(&mut self) -> KeyMode{
+		match self.view{
+			ModelView::Menu => {
+				if self.renaming.is_some() {
+					KeyMode::Edit
+				}else{
+					KeyMode::List
+				}
+			},
+			ModelView::List => {
+				if self.page_filter.is_some() {
+					KeyMode::Edit
+				}else{
+					KeyMode::List
+				}
+			},
+			ModelView::Page => {
+				if self.selected_is_text_entry() {
+					KeyMode::Edit
+				}else{
+					KeyMode::Page
+				}
+			},
+			ModelView::Datasets => KeyMode::List,
+			ModelView::Traffic => {
+				if self.is_filtering_traffic() {
+					KeyMode::Edit
+				}else{
+					KeyMode::List
+				}
+			},
+		}
+	}
+
+	pub(crate) fn perform(&mut self, action: Action){
+		match action{
+			Action::Quit => self.exit = Some(ModelExit::Quit),
+			Action::SwitchProfile => self.exit = Some(ModelExit::SwitchProfile),
+			Action::ToggleDebug => self.debug = !self.debug,
+			Action::OpenMenu => {
+				match self.view{
+					ModelView::Menu => {
+						if self.renaming.take().is_none() {
+							self.view = ModelView::List;
+						}
+					},
+					ModelView::List => {
+						if self.page_filter.take().is_none() {
+							self.view = ModelView::Menu;
+						}
+					},
+					ModelView::Page => self.view = ModelView::Menu,
+					ModelView::Datasets => self.view = ModelView::Menu,
+					ModelView::Traffic => {
+						if !self.clear_traffic_filter() {
+							self.view = ModelView::Menu;
+						}
+					},
+				}
+			},
+			Action::Submit => {
+				match self.view{
+					ModelView::Menu => {
+						if self.renaming.is_some() {
+							self.finish_rename();
+						}else{
+							self.activate_menu_entry();
+						}
+					},
+					ModelView::List => self.open_selected_page(),
+					ModelView::Page => self.submit_selected(),
+					ModelView::Datasets => self.expand_dataset_row(None),
+					ModelView::Traffic => {}, // the selected message is always shown
+				}
+			},
+			Action::Filter => {
+				match self.view{
+					ModelView::List => self.start_page_filter(),
+					ModelView::Traffic => self.start_traffic_filter(),
+					_ => {},
+				}
+			},
+			Action::TogglePause => {
+				if let ModelView::Traffic = self.view {
+					self.toggle_traffic_pause();
+				}
+			},
+			Action::Export => {
+				if let ModelView::Traffic = self.view {
+					self.export_traffic();
+				}
+			},
+			Action::CycleSort => {
+				if let ModelView::List = self.view {
+					self.cycle_page_sort();
+				}
+			},
+			Action::NextTab => self.cycle_tab(true),
+			Action::PrevTab => self.cycle_tab(false),
+			Action::CloseTab => self.close_tab(),
+			Action::SplitSideBySide => self.split(SplitDirection::SideBySide),
+			Action::SplitStacked => self.split(SplitDirection::Stacked),
+			Action::Unsplit => self.unsplit(),
+			Action::FocusNextPane => self.focus_next_pane(),
+			Action::GrowPane => self.resize_pane(true),
+			Action::ShrinkPane => self.resize_pane(false),
+			Action::NavigateUp => {
+				match self.view{
+					ModelView::Menu => self.select_prev_menu_entry(),
+					ModelView::List => self.move_visible_selection(true),
+					ModelView::Page => self.navigate(SelectDirection::Up),
+					ModelView::Datasets => self.select_prev_dataset_row(),
+					ModelView::Traffic => self.move_traffic_selection(-1),
+				}
+			},
+			Action::NavigateDown => {
+				match self.view{
+					ModelView::Menu => self.select_next_menu_entry(),
+					ModelView::List => self.move_visible_selection(false),
+					ModelView::Page => self.navigate(SelectDirection::Down),
+					ModelView::Datasets => self.select_next_dataset_row(),
+					ModelView::Traffic => self.move_traffic_selection(1),
+				}
+			},
+			Action::NavigateLeft => {
+				match self.view{
+					ModelView::Datasets => self.expand_dataset_row(Some(false)),
+					_ => self.navigate(SelectDirection::Left),
+				}
+			},
+			Action::NavigateRight => {
+				match self.view{
+					ModelView::Datasets => self.expand_dataset_row(Some(true)),
+					_ => self.navigate(SelectDirection::Right),
+				}
+			},
+			Action::ScrollPageUp => {
+				if let Some(state) = self.get_viewed_page_state() {
+					state.scroll_page_up();
+				}
+			},
+			Action::ScrollPageDown => {
+				if let Some(state) = self.get_viewed_page_state() {
+					state.scroll_page_down();
+				}
+			},
+			Action::ScrollTop => {
+				if let Some(state) = self.get_viewed_page_state() {
+					state.scroll_to_top();
+				}
+			},
+			Action::ScrollBottom => {
+				if let Some(state) = self.get_viewed_page_state() {
+					state.scroll_to_bottom();
+				}
+			},
+			// move within the text being edited, leave the element at its edges
+			Action::CursorLeft => {
+				if !self.edit_selected_input(EditOp::Left) {
+					self.navigate(SelectDirection::Left);
+				}
+			},
+			Action::CursorRight => {
+				if !self.edit_selected_input(EditOp::Right) {
+					self.navigate(SelectDirection::Right);
+				}
+			},
+			Action::CursorHome => {self.edit_selected_input(EditOp::Home);},
+			Action::CursorEnd => {self.edit_selected_input(EditOp::End);},
+			Action::DeleteBack => {self.edit_selected_input(EditOp::Backspace);},
+			Action::DeleteForward => {self.edit_selected_input(EditOp::Delete);},
+			Action::DeleteWord => {self.edit_selected_input(EditOp::DeleteWord);},
+			Action::KillLine => {self.edit_selected_input(EditOp::KillLine);},
+		}
+	}
+
+	// move the selection on the current page
+	pub(crate) fn navigate(&mut self, direction: SelectDirection){
+		if let ModelView::Page = self.view {
+			if let Some((mgr, state, data_map)) = self.get_context(){
+				state.select_next(mgr, data_map, direction);
+			}
+		}
+	}
+
+	// the state of the page being viewed, if there is one
+	pub(crate) fn get_viewed_page_state(&mut self) -> Option<&mut PageState>{
+		match self.view{
+			ModelView::Menu => None,
+			ModelView::List => None,
+			ModelView::Page => self.get_current_mgr_state_mut().map(|(_, state)| state),
+			ModelView::Datasets => None,
+			ModelView::Traffic => None,
+		}
+	}
+}
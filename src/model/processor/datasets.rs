@@ -0,0 +1,247 @@
+// A browser for the raw contents of every dataset, to tell whether a page or its data is at fault.
+// Each dataset can be expanded into its entries, and each entry into a tree of its values.
+
+use std::collections::{HashMap, HashSet, VecDeque};
+
+use chrono::{DateTime, Duration, Local};
+use serde_json::Value;
+use spider_client::message::{AbsoluteDatasetPath, DatasetData, DatasetMessage};
+
+use crate::renderer::Renderer;
+
+use super::ModelProcessor;
+
+
+
+// how long changed entries stay highlighted
+const CHANGE_SECONDS: i64 = 5;
+// how many of the other dataset messages are kept
+const MAX_MESSAGES: usize = 20;
+// the id of the row listing them
+const MESSAGES_ID: &str = "messages";
+
+// A line of the dataset browser
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InspectorRow{
+	pub depth: usize,
+	pub text: String,
+	pub expandable: bool,
+	pub expanded: bool,
+	pub changed: bool, // changed in the last few seconds
+}
+
+// When each dataset last changed, and which of its entries changed then
+pub(crate) struct DatasetHistory{
+	time: DateTime<Local>,
+	changed: HashSet<usize>,
+}
+
+#[derive(Default)]
+pub(crate) struct DatasetInspector{
+	history: HashMap<AbsoluteDatasetPath, DatasetHistory>,
+	expanded: HashSet<String>, // ids of the expanded rows
+	selected: usize,
+	messages: VecDeque<(DateTime<Local>, String)>, // dataset messages other than whole datasets
+	message_count: usize,
+}
+
+impl DatasetInspector{
+	// note which entries differ from what was there before, returns false if none do.
+	// Data sent again unchanged keeps the last change, and its highlight, as they were
+	fn record(&mut self, path: &AbsoluteDatasetPath, old: Option<&[DatasetData]>, data: &[DatasetData]) -> bool{
+		let changed: HashSet<usize> = data.iter().enumerate()
+			.filter(|(i, datum)| old.and_then(|old| old.get(*i)) != Some(*datum))
+			.map(|(i, _)| i)
+			.collect();
+		let any_changed = !changed.is_empty() || old.map(|old| old.len()) != Some(data.len());
+		if any_changed {
+			let history = DatasetHistory {
+				time: Local::now(),
+				changed,
+			};
+			self.history.insert(path.clone(), history);
+		}
+		any_changed
+	}
+
+	// the last change to a dataset, while it is still highlighted
+	fn recent_change(&self, path: &AbsoluteDatasetPath, now: DateTime<Local>) -> Option<&DatasetHistory>{
+		self.history.get(path)
+			.filter(|history| now - history.time < Duration::seconds(CHANGE_SECONDS))
+	}
+
+	// when the next highlight runs out, if any are still showing
+	pub(crate) fn next_expiry(&self, now: DateTime<Local>) -> Option<DateTime<Local>>{
+		self.history.values()
+			.filter(|history| !history.changed.is_empty())
+			.map(|history| history.time + Duration::seconds(CHANGE_SECONDS))
+			.filter(|expiry| *expiry > now)
+			.min()
+	}
+}
+
+impl<R: Renderer> ModelProcessor<R>{
+	// dataset messages the interface does not apply, listed in the browser below the datasets
+	pub(crate) fn record_dataset_message(&mut self, msg: &DatasetMessage){
+		let inspector = &mut self.inspector;
+		if inspector.messages.len() >= MAX_MESSAGES {
+			inspector.messages.pop_front();
+		}
+		inspector.messages.push_back((Local::now(), format!("{:?}", msg)));
+		inspector.message_count += 1;
+	}
+
+	// note which entries of a dataset differ from what was there before, returns false if none do
+	pub(crate) fn record_dataset_update(&mut self, path: &AbsoluteDatasetPath, data: &[DatasetData]) -> bool{
+		let old = self.datasets.get(path).map(|old| old.as_slice());
+		self.inspector.record(path, old, data)
+	}
+
+	// the rows of the browser, with the id of each
+	pub(crate) fn inspector_rows(&self) -> Vec<(String, InspectorRow)>{
+		let now = Local::now();
+		let mut paths: Vec<&AbsoluteDatasetPath> = self.datasets.keys().collect();
+		paths.sort_by_key(|path| format!("{:?}", path));
+
+		let mut rows = Vec::new();
+		for path in paths{
+			let data = &self.datasets[path];
+			let id = format!("{:?}", path);
+			let history = self.inspector.recent_change(path, now);
+			let updated = match self.inspector.history.get(path){
+				Some(history) => history.time.format("%H:%M:%S").to_string(),
+				None => String::from("-"),
+			};
+			let expanded = self.inspector.expanded.contains(&id);
+			rows.push((id.clone(), InspectorRow {
+				depth: 0,
+				text: format!("{:?}  {} entries, updated {}", path, data.len(), updated),
+				expandable: !data.is_empty(),
+				expanded,
+				changed: history.map(|history| !history.changed.is_empty()).unwrap_or(false),
+			}));
+			if !expanded {
+				continue;
+			}
+			for (i, datum) in data.iter().enumerate(){
+				let changed = history.map(|history| history.changed.contains(&i)).unwrap_or(false);
+				let value = to_value(datum);
+				self.push_value_rows(&mut rows, format!("{}#{}", id, i), format!("[{}]", i), &value, 1, changed);
+			}
+		}
+
+		let messages = &self.inspector.messages;
+		if !messages.is_empty() {
+			let id = String::from(MESSAGES_ID);
+			let expanded = self.inspector.expanded.contains(&id);
+			rows.push((id.clone(), InspectorRow {
+				depth: 0,
+				text: format!("Other dataset messages  {} received, the last {} kept", self.inspector.message_count, messages.len()),
+				expandable: true,
+				expanded,
+				changed: false,
+			}));
+			if expanded {
+				for (i, (time, text)) in messages.iter().enumerate(){
+					rows.push((format!("{}#{}", id, i), InspectorRow {
+						depth: 1,
+						text: format!("{} {}", time.format("%H:%M:%S"), text),
+						expandable: false,
+						expanded: false,
+						changed: false,
+					}));
+				}
+			}
+		}
+		rows
+	}
+
+	fn push_value_rows(&self, rows: &mut Vec<(String, InspectorRow)>, id: String, label: String, value: &Value, depth: usize, changed: bool){
+		let expanded = self.inspector.expanded.contains(&id);
+		let (text, expandable) = match value{
+			Value::Object(map) => (format!("{} {{{}}}", label, map.len()), !map.is_empty()),
+			Value::Array(items) => (format!("{} [{}]", label, items.len()), !items.is_empty()),
+			scalar => (format!("{}: {}", label, scalar), false),
+		};
+		rows.push((id.clone(), InspectorRow { depth, text, expandable, expanded, changed }));
+		if !expanded {
+			return;
+		}
+		match value{
+			Value::Object(map) => {
+				for (key, child) in map.iter(){
+					self.push_value_rows(rows, format!("{}/{}", id, key), key.clone(), child, depth + 1, changed);
+				}
+			},
+			Value::Array(items) => {
+				for (i, child) in items.iter().enumerate(){
+					self.push_value_rows(rows, format!("{}/{}", id, i), format!("[{}]", i), child, depth + 1, changed);
+				}
+			},
+			_ => {},
+		}
+	}
+
+	pub(crate) fn inspector_selected(&self, row_count: usize) -> usize{
+		self.inspector.selected.min(row_count.saturating_sub(1))
+	}
+
+	pub(crate) fn select_prev_dataset_row(&mut self){
+		let count = self.inspector_rows().len();
+		self.inspector.selected = self.inspector_selected(count).saturating_sub(1);
+	}
+
+	pub(crate) fn select_next_dataset_row(&mut self){
+		let count = self.inspector_rows().len();
+		self.inspector.selected = (self.inspector_selected(count) + 1).min(count.saturating_sub(1));
+	}
+
+	pub(crate) fn select_dataset_row(&mut self, index: usize){
+		self.inspector.selected = index;
+	}
+
+	// expand or collapse the selected row. Collapsing a row that is not expanded moves to its parent
+	pub(crate) fn expand_dataset_row(&mut self, expand: Option<bool>){
+		let rows = self.inspector_rows();
+		let selected = self.inspector_selected(rows.len());
+		let (id, row) = match rows.get(selected){
+			Some(row) => row,
+			None => return,
+		};
+		let expand = expand.unwrap_or(!row.expanded);
+		if expand && row.expandable {
+			self.inspector.expanded.insert(id.clone());
+		}else if !expand && row.expanded {
+			self.inspector.expanded.remove(id);
+		}else if !expand {
+			let parent = rows[..selected].iter().rposition(|(_, parent)| parent.depth < row.depth);
+			if let Some(parent) = parent {
+				self.inspector.selected = parent;
+			}
+		}
+	}
+}
+
+fn to_value(datum: &DatasetData) -> Value{
+	serde_json::to_value(datum).unwrap_or(Value::Null)
+}
+
+#[cfg(test)]
+mod tests{
+	use super::*;
+
+	#[test]
+	fn resending_keeps_the_highlight(){
+		let path = AbsoluteDatasetPath::new_public(vec![String::from("items")]);
+		let before = vec![DatasetData::String(String::from("one")), DatasetData::String(String::from("two"))];
+		let after = vec![DatasetData::String(String::from("one")), DatasetData::String(String::from("three"))];
+		let mut inspector = DatasetInspector::default();
+
+		assert!(inspector.record(&path, None, &before));
+		assert!(inspector.record(&path, Some(&before), &after));
+		assert!(!inspector.record(&path, Some(&after), &after));
+
+		let change = inspector.recent_change(&path, Local::now()).expect("the change is still recent");
+		assert_eq!(change.changed, HashSet::from([1]));
+	}
+}
@@ -0,0 +1,108 @@
+// Editing operations for uncommitted text inputs. Cursor positions are counted in chars.
+
+
+#[derive(Debug, Clone)]
+pub enum EditOp{
+    Insert(String),
+    Backspace,
+    Delete,
+    Left,
+    Right,
+    Home,
+    End,
+    DeleteWord,
+    KillLine,
+}
+
+// apply an edit to the text, returns false if the edit could not do anything
+// (such as moving left from the start of the text)
+pub fn apply(text: &mut String, cursor: &mut usize, op: EditOp) -> bool{
+    let len = text.chars().count();
+    if *cursor > len {
+        *cursor = len;
+    }
+    match op{
+        EditOp::Insert(s) => {
+            if s.is_empty() {
+                return false;
+            }
+            text.insert_str(byte_index(text, *cursor), &s);
+            *cursor += s.chars().count();
+            true
+        },
+        EditOp::Backspace => {
+            if *cursor == 0 {
+                return false;
+            }
+            *cursor -= 1;
+            text.remove(byte_index(text, *cursor));
+            true
+        },
+        EditOp::Delete => {
+            if *cursor >= len {
+                return false;
+            }
+            text.remove(byte_index(text, *cursor));
+            true
+        },
+        EditOp::Left => {
+            if *cursor == 0 {
+                return false;
+            }
+            *cursor -= 1;
+            true
+        },
+        EditOp::Right => {
+            if *cursor >= len {
+                return false;
+            }
+            *cursor += 1;
+            true
+        },
+        EditOp::Home => {
+            let moved = *cursor != 0;
+            *cursor = 0;
+            moved
+        },
+        EditOp::End => {
+            let moved = *cursor != len;
+            *cursor = len;
+            moved
+        },
+        EditOp::DeleteWord => {
+            // remove whitespace before the cursor, then the word before that
+            let chars: Vec<char> = text.chars().collect();
+            let mut start = *cursor;
+            while start > 0 && chars[start - 1].is_whitespace() {
+                start -= 1;
+            }
+            while start > 0 && !chars[start - 1].is_whitespace() {
+                start -= 1;
+            }
+            if start == *cursor {
+                return false;
+            }
+            let range = byte_index(text, start)..byte_index(text, *cursor);
+            text.replace_range(range, "");
+            *cursor = start;
+            true
+        },
+        EditOp::KillLine => {
+            // remove everything before the cursor
+            if *cursor == 0 {
+                return false;
+            }
+            let range = 0..byte_index(text, *cursor);
+            text.replace_range(range, "");
+            *cursor = 0;
+            true
+        },
+    }
+}
+
+fn byte_index(text: &str, char_index: usize) -> usize{
+    match text.char_indices().nth(char_index) {
+        Some((i, _)) => i,
+        None => text.len(),
+    }
+}
@@ -0,0 +1,187 @@
+// The main menu, a home for everything that is not a page.
+
+use spider_client::message::{Message, RouterMessage};
+use tracing::warn;
+
+use crate::{renderer::Renderer, model::ModelExit, identity::TemplateVars};
+
+use super::{ModelProcessor, ModelView};
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum MenuEntry{
+	Pages,
+	Connection,
+	Identity,
+	Datasets,
+	Traffic,
+	Settings,
+	Logs,
+	About,
+	Profiles,
+	Quit,
+}
+
+pub(crate) const MENU_ENTRIES: [MenuEntry; 10] = [
+	MenuEntry::Pages,
+	MenuEntry::Connection,
+	MenuEntry::Identity,
+	MenuEntry::Datasets,
+	MenuEntry::Traffic,
+	MenuEntry::Settings,
+	MenuEntry::Logs,
+	MenuEntry::About,
+	MenuEntry::Profiles,
+	MenuEntry::Quit,
+];
+
+impl MenuEntry{
+	pub(crate) fn label(&self) -> &'static str{
+		match self{
+			MenuEntry::Pages => "Pages",
+			MenuEntry::Connection => "Connection",
+			MenuEntry::Identity => "Identity",
+			MenuEntry::Datasets => "Datasets",
+			MenuEntry::Traffic => "Traffic",
+			MenuEntry::Settings => "Settings",
+			MenuEntry::Logs => "Logs",
+			MenuEntry::About => "About",
+			MenuEntry::Profiles => "Switch Profile",
+			MenuEntry::Quit => "Quit",
+		}
+	}
+}
+
+impl<R: Renderer> ModelProcessor<R>{
+	// finish typing a new identity name, and tell the router
+	pub(crate) fn finish_rename(&mut self){
+		let name = match self.renaming.take(){
+			Some((name, _)) => name.trim().to_string(),
+			None => return,
+		};
+		if name.is_empty() || name == self.identity {
+			return;
+		}
+		self.identity = name;
+		let msg = RouterMessage::SetIdentityProperty("name".into(), self.identity.clone());
+		if self.sender.blocking_send(Message::Router(msg)).is_err() {
+			warn!("Failed to send the new identity name, the connection has closed");
+		}
+		self.add_notice(format!("Renamed to {}", self.identity));
+	}
+
+	pub(crate) fn selected_menu_entry(&self) -> MenuEntry{
+		MENU_ENTRIES[self.menu_index.min(MENU_ENTRIES.len() - 1)]
+	}
+
+	pub(crate) fn select_prev_menu_entry(&mut self){
+		self.menu_index = self.menu_index.saturating_sub(1);
+	}
+
+	pub(crate) fn select_next_menu_entry(&mut self){
+		self.menu_index = (self.menu_index + 1).min(MENU_ENTRIES.len() - 1);
+	}
+
+	pub(crate) fn activate_menu_entry(&mut self){
+		match self.selected_menu_entry(){
+			MenuEntry::Pages => self.view = ModelView::List,
+			MenuEntry::Datasets => self.view = ModelView::Datasets,
+			MenuEntry::Traffic => self.view = ModelView::Traffic,
+			MenuEntry::Identity => {
+				let name = self.identity.clone();
+				let cursor = name.chars().count();
+				self.renaming = Some((name, cursor));
+			},
+			MenuEntry::Profiles => self.exit = Some(ModelExit::SwitchProfile),
+			MenuEntry::Quit => self.exit = Some(ModelExit::Quit),
+			// these only show information
+			MenuEntry::Connection | MenuEntry::Settings | MenuEntry::Logs | MenuEntry::About => {},
+		}
+	}
+
+	// the text shown beside the menu for the selected entry
+	pub(crate) fn menu_details(&self) -> Vec<String>{
+		match self.selected_menu_entry(){
+			MenuEntry::Pages => {
+				let pages = self.page_set.get_page_vec();
+				let mut lines = vec![format!("{} pages", pages.len()), String::new()];
+				lines.extend(pages.iter().map(|page| page.name().clone()));
+				lines
+			},
+			MenuEntry::Connection => {
+				vec![
+					format!("Status: {}", self.connection),
+					format!("Notices: {}", self.notices.len()),
+				]
+			},
+			MenuEntry::Identity => {
+				let mut lines = match &self.renaming{
+					Some((name, cursor)) => {
+						// mark the cursor, the menu has no text entry of its own
+						let mut name = name.clone();
+						let index = name.char_indices().nth(*cursor).map(|(i, _)| i).unwrap_or(name.len());
+						name.insert(index, '|');
+						vec![format!("New name: {}", name), String::from("enter to rename, esc to cancel")]
+					},
+					None => vec![format!("Name: {}", self.identity), String::from("enter to rename")],
+				};
+				let vars = TemplateVars::new(&self.profile_name);
+				if !self.config.identity.properties.is_empty() {
+					lines.push(String::new());
+				}
+				for (key, value) in self.config.identity.properties.iter(){
+					lines.push(format!("{}: {}", key, vars.expand(value)));
+				}
+				lines
+			},
+			MenuEntry::Datasets => {
+				if self.datasets.is_empty() {
+					return vec![String::from("No datasets have been received")];
+				}
+				let mut lines: Vec<String> = self.datasets.iter()
+					.map(|(path, data)| format!("{:?}: {} items", path, data.len()))
+					.collect();
+				lines.sort();
+				lines.push(String::new());
+				lines.push(String::from("enter to browse"));
+				lines
+			},
+			MenuEntry::Traffic => {
+				vec![
+					String::from("Messages passed between the base and this interface"),
+					format!("Keeping the last {}", self.config.traffic.capacity),
+					format!("Exported to: {}", self.config.traffic.export_path),
+					String::new(),
+					String::from("enter to open"),
+				]
+			},
+			MenuEntry::Settings => {
+				vec![
+					format!("Key bindings: {:?}", self.config.keybindings.preset),
+					format!("Theme: {}", self.config.theme.name),
+					format!("Log file: {}", self.config.log_path),
+					format!("Log level: {}", self.config.logging.level),
+				]
+			},
+			MenuEntry::Logs => {
+				let mut lines = vec![format!("Log file: {}", self.config.log_path), String::new()];
+				if self.notices.is_empty() {
+					lines.push(String::from("No notices"));
+				}
+				for notice in self.notices.iter().rev(){
+					lines.push(format!("[{}] {}", notice.time.format("%H:%M:%S"), notice.text));
+				}
+				lines
+			},
+			MenuEntry::About => {
+				vec![
+					format!("{} {}", env!("CARGO_PKG_NAME"), env!("CARGO_PKG_VERSION")),
+					String::from("A terminal interface for a Spider base"),
+				]
+			},
+			MenuEntry::Profiles => vec![String::from("Disconnect from this base and choose another")],
+			MenuEntry::Quit => vec![String::from("Disconnect and exit")],
+		}
+	}
+}
@@ -3,23 +3,44 @@ use spider_client::{
     Relation, SpiderId2048,
 };
 
-use crate::renderer::Renderer;
+use crate::{renderer::{Renderer, HitMap}, config::{SpiderTuiConfig, Profile}, keymap::Keymap, identity::TemplateVars};
 
 mod page_state;
-pub use self::page_state::PageState;
+pub use self::page_state::{PageState, SelectDirection, grid_dimensions};
 
-use super::update::ModelUpdate;
+use super::{ModelExit, update::{ModelUpdate, ConnectionState, Notice}, session::{SessionHeader, SessionRecorder}};
 
-use std::{thread::{spawn, JoinHandle}, collections::HashMap};
-use tokio::sync::mpsc::{Receiver, Sender};
+use std::{thread::{spawn, sleep, JoinHandle}, collections::{HashMap, VecDeque}, path::PathBuf, time::{Duration, Instant}};
+use chrono::{DateTime, Local};
+use tokio::{runtime::Handle, sync::mpsc::{Receiver, Sender}, time::timeout};
+use tracing::{info, info_span, instrument, warn};
 
 mod update;
+mod action;
+mod menu;
+pub(crate) mod page_list;
+mod recent;
+mod tabs;
+pub(crate) mod split;
+pub(crate) mod datasets;
+pub(crate) mod traffic;
+pub(crate) mod status;
+pub(crate) mod line_edit;
 
 pub(crate) mod state;
 
+use self::{page_list::PageSort, recent::RecentPages, split::Split, datasets::{DatasetInspector, InspectorRow}, traffic::TrafficMonitor};
+
+const MAX_NOTICES: usize = 50;
+// the most queued updates taken in before drawing, so that a flood of them can not hold the screen back
+const MAX_COALESCED: usize = 256;
+
 enum ModelView {
+    Menu,
     List,
     Page,
+    Datasets,
+    Traffic,
 }
 
 pub struct ModelProcessor<R: Renderer> {
@@ -29,15 +50,45 @@ pub struct ModelProcessor<R: Renderer> {
 
     // view
     view: ModelView,
+    keymap: Keymap,
+    menu_index: usize,
+    config: SpiderTuiConfig,
 
     // page rendering
     page_set: UiPageList,
     page_states: HashMap<SpiderId2048, PageState>,
+    page_filter: Option<(String, usize)>, // the filter being typed, and its cursor
+    page_sort: PageSort,
+    recent_pages: RecentPages,
+    tabs: Vec<SpiderId2048>, // pages open as tabs
+    active_tab: usize,
+    restore_tabs: Vec<String>, // tabs from the last session, until the pages arrive
+    restore_active: Option<String>,
+    split: Option<Split>,
 
     // Datasets
     datasets: HashMap<AbsoluteDatasetPath, Vec<DatasetData>>,
+    inspector: DatasetInspector,
+    traffic: TrafficMonitor,
+
+    // Connection to base
+    own_id: SpiderId2048, // the id of this client, and of any page it serves
+    connection: ConnectionState,
+    notices: VecDeque<Notice>,
+    profile_name: String,
+    base_address: Option<String>,
+    identity: String,
+    renaming: Option<(String, usize)>, // new identity name being typed, and its cursor
+    last_update: Option<DateTime<Local>>,
+    debug: bool,
 
-    exit: bool,
+    // layout of the last render, for mouse input
+    hit_map: HitMap,
+    dirty: bool, // something on screen has changed since the last render
+
+    recorder: Option<SessionRecorder>,
+
+    exit: Option<ModelExit>,
 }
 
 impl<R: Renderer> ModelProcessor<R> {
@@ -46,8 +97,12 @@ impl<R: Renderer> ModelProcessor<R> {
         sender: Sender<Message>,
         renderer: R,
         relation: Relation,
+        config: &SpiderTuiConfig,
+        profile: &Profile,
     ) -> Self {
-
+        let recent_pages = RecentPages::load(PathBuf::from(&config.page_list.recent_path), &profile.name);
+        let (restore_tabs, active_tab) = recent_pages.tabs();
+        let restore_active = restore_tabs.get(active_tab).cloned();
 
         Self {
             receiver,
@@ -55,66 +110,246 @@ impl<R: Renderer> ModelProcessor<R> {
             renderer: Some(renderer),
 
             view: ModelView::List,
+            keymap: Keymap::from_config(&config.keybindings),
+            menu_index: 0,
+            config: config.clone(),
 
             page_set: UiPageList::new(),
             page_states: HashMap::new(),
+            page_filter: None,
+            page_sort: config.page_list.sort,
+            recent_pages,
+            tabs: Vec::new(),
+            active_tab: 0,
+            restore_tabs,
+            restore_active,
+            split: None,
 
             datasets: HashMap::new(),
+            inspector: DatasetInspector::default(),
+            traffic: TrafficMonitor::default(),
 
-            exit: false,
+            own_id: relation.id,
+            connection: ConnectionState::Connected,
+            notices: VecDeque::new(),
+            profile_name: profile.name.clone(),
+            base_address: profile.addresses.first().cloned(),
+            identity: TemplateVars::new(&profile.name).expand(&config.identity.name),
+            renaming: None,
+            last_update: None,
+            debug: config.status_bar.debug,
+
+            hit_map: HitMap::new(),
+            dirty: true,
+
+            recorder: None,
+
+            exit: None,
         }
     }
 
-    pub fn start(mut self) -> JoinHandle<Result<(), std::io::Error>> {
-        spawn(move || -> Result<(), std::io::Error> {
+    pub fn start(mut self) -> JoinHandle<Result<ModelExit, std::io::Error>> {
+        let runtime = Handle::current();
+        spawn(move || -> Result<ModelExit, std::io::Error> {
+            let _span = info_span!("model_processor").entered();
             let mut renderer = match self.renderer.take() {
                 Some(renderer) => renderer,
-                None => return Ok(()),
+                None => return Ok(ModelExit::Quit),
             };
 
-            // set name
-            let msg = RouterMessage::SetIdentityProperty("name".into(), "TUI".into());
-            let msg = Message::Router(msg);
-            self.sender.blocking_send(msg).unwrap();
-
-            // subscribe to ui
-            let msg = Message::Ui(UiMessage::Subscribe);
-            self.sender.blocking_send(msg).unwrap();
+            self.start_recording();
+            self.announce();
 
             renderer.startup();
 
+            let frame_interval = match self.config.frame_rate {
+                0 => Duration::ZERO,
+                rate => Duration::from_secs(1) / rate,
+            };
+            let mut last_frame: Option<Instant> = None;
             loop {
-                let update = match self.receiver.blocking_recv() {
-                    Some(update) => update,
-                    None => break, // No more messages, exit the model/renderer
+                // wake up when a notice or highlight runs out, to draw the screen without it
+                let received = match self.next_expiry() {
+                    Some(wait) => runtime.block_on(timeout(wait, self.receiver.recv())).ok(),
+                    None => Some(self.receiver.blocking_recv()),
                 };
-                self.update(update);
+                match received {
+                    Some(Some(update)) => {
+                        self.process(update);
+                        // a burst of updates is drawn once
+                        self.take_queued();
+                    },
+                    Some(None) => break, // No more messages, exit the model/renderer
+                    None => self.dirty = true,
+                }
 
-                self.render(&mut renderer);
+                // keep to the frame rate, taking in whatever arrives meanwhile
+                if let Some(last_frame) = last_frame {
+                    let wait = frame_interval.saturating_sub(last_frame.elapsed());
+                    if self.dirty && self.exit.is_none() && !wait.is_zero() {
+                        sleep(wait);
+                        self.take_queued();
+                    }
+                }
 
-                if self.exit {
+                if self.exit.is_some() {
                     break;
                 }
+                if self.dirty {
+                    self.render(&mut renderer);
+                    last_frame = Some(Instant::now());
+                }
             }
 
             renderer.shutdown();
 
-            Ok(())
+            Ok(self.exit.unwrap_or(ModelExit::Quit))
         })
     }
 
+    fn process(&mut self, update: ModelUpdate){
+        if let Some(recorder) = &mut self.recorder {
+            recorder.record(&update);
+        }
+        self.update(update);
+    }
+
+    // apply some of the updates that are already waiting, until the model exits
+    fn take_queued(&mut self){
+        for _ in 0..MAX_COALESCED {
+            if self.exit.is_some() {
+                break;
+            }
+            match self.receiver.try_recv() {
+                Ok(update) => self.process(update),
+                Err(_) => break, // none waiting, or the channel has closed
+            }
+        }
+    }
+
+    // write every update to the session file, if one is configured
+    fn start_recording(&mut self){
+        let path = match &self.config.record_path{
+            Some(path) => PathBuf::from(path),
+            None => return,
+        };
+        let (tabs, active_tab) = self.recent_pages.tabs();
+        let header = SessionHeader {
+            profile: self.profile_name.clone(),
+            config: Some(self.config.clone()),
+            tabs,
+            active_tab,
+        };
+        match SessionRecorder::create(&path, &header){
+            Ok(recorder) => {
+                info!("Recording the session to {}", path.display());
+                self.recorder = Some(recorder);
+            },
+            Err(e) => self.add_notice(format!("Failed to record the session to {}: {}", path.display(), e)),
+        }
+    }
+
+    // start where a recorded session started, without changing the saved pages
+    pub(crate) fn restore_session(&mut self, header: &SessionHeader){
+        self.recent_pages = RecentPages::in_memory(&header.profile, header.tabs.clone(), header.active_tab);
+        self.restore_tabs = header.tabs.clone();
+        self.restore_active = header.tabs.get(header.active_tab).cloned();
+        self.profile_name = header.profile.clone();
+        self.identity = TemplateVars::new(&header.profile).expand(&self.config.identity.name);
+    }
+
+    // identify to the router and subscribe to ui updates, repeated after each reconnection
+    pub(crate) fn announce(&mut self){
+        // set name
+        let msg = RouterMessage::SetIdentityProperty("name".into(), self.identity.clone());
+        let msg = Message::Router(msg);
+        if self.sender.blocking_send(msg).is_err() {
+            warn!("Failed to announce to the router, the connection has closed");
+            return;
+        }
+
+        // other properties
+        let vars = TemplateVars::new(&self.profile_name);
+        for (key, value) in self.config.identity.properties.iter(){
+            let msg = RouterMessage::SetIdentityProperty(key.clone(), vars.expand(value));
+            if self.sender.blocking_send(Message::Router(msg)).is_err() {
+                warn!("Failed to announce to the router, the connection has closed");
+                return;
+            }
+        }
+
+        // subscribe to ui
+        let msg = Message::Ui(UiMessage::Subscribe);
+        if self.sender.blocking_send(msg).is_err() {
+            warn!("Failed to subscribe to pages, the connection has closed");
+        }
+    }
+
+    // how long until something on screen runs out
+    fn next_expiry(&self) -> Option<Duration>{
+        let now = Local::now();
+        let notice = self.notices.back().map(|notice| notice.expires()).filter(|expiry| *expiry > now);
+        let highlight = match self.view {
+            ModelView::Datasets => self.inspector.next_expiry(now),
+            _ => None,
+        };
+        let expiry = notice.into_iter().chain(highlight).min()?;
+        (expiry - now).to_std().ok()
+    }
+
+    // keep a short history of notices for the user
+    pub(crate) fn add_notice(&mut self, text: String){
+        if self.notices.len() >= MAX_NOTICES {
+            self.notices.pop_front();
+        }
+        self.notices.push_back(Notice::new(text));
+    }
+
+    #[instrument(level = "trace", skip_all)]
     pub(crate) fn render(&mut self, renderer: &mut R){
+		self.dirty = false;
+		let status = self.status();
+		renderer.set_status(&status);
+		renderer.set_notice(self.notices.back());
+		renderer.set_tabs(&self.tab_names(), self.active_tab);
 
 		match self.view{
+			ModelView::Menu => {
+				let entries: Vec<&str> = menu::MENU_ENTRIES.iter().map(|entry| entry.label()).collect();
+				self.hit_map = renderer.render_menu(&entries, self.menu_index, &self.menu_details());
+			},
 			ModelView::List => {
-				renderer.render_page_list(&self.page_set.get_page_vec(), self.page_set.selected_index());
+				let hit_map = renderer.render_page_list(&self.page_list_view());
+				self.hit_map = hit_map;
+			},
+			ModelView::Datasets => {
+				let rows: Vec<InspectorRow> = self.inspector_rows().into_iter().map(|(_, row)| row).collect();
+				let selected = self.inspector_selected(rows.len());
+				self.hit_map = renderer.render_datasets(&rows, selected);
+			},
+			ModelView::Traffic => {
+				let hit_map = renderer.render_traffic(&self.traffic_view());
+				self.hit_map = hit_map;
 			},
 			ModelView::Page => {
+				if self.split.is_some() {
+					match self.render_split(renderer){
+						Some(hit_map) => {
+							self.hit_map = hit_map;
+							return;
+						},
+						None => self.split = None, // one of the pages is gone
+					}
+				}
 				match self.get_context(){
-					Some((mgr, state, data_map)) => renderer.render_page(mgr.get_page(), state, data_map),
+					Some((mgr, state, data_map)) => {
+						let hit_map = renderer.render_page(mgr.get_page(), state, data_map);
+						self.hit_map = hit_map;
+					},
 					None => {
 						self.view = ModelView::List;
-						renderer.render_page_list(&self.page_set.get_page_vec(), self.page_set.selected_index());
+						let hit_map = renderer.render_page_list(&self.page_list_view());
+						self.hit_map = hit_map;
 					},
 				}
 			},
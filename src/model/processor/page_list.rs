@@ -0,0 +1,269 @@
+// Filtering, ordering and quick selection in the page list.
+
+use serde::{Serialize, Deserialize};
+use spider_client::message::UiPage;
+
+use crate::renderer::Renderer;
+
+use super::{ModelProcessor, line_edit::{self, EditOp}};
+
+
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PageSort{
+	#[default]
+	Base, // in the order the base sent them
+	Name,
+	Owner,
+	Recent,
+}
+
+impl PageSort{
+	fn next(&self) -> Self{
+		match self{
+			PageSort::Base => PageSort::Name,
+			PageSort::Name => PageSort::Owner,
+			PageSort::Owner => PageSort::Recent,
+			PageSort::Recent => PageSort::Base,
+		}
+	}
+
+	pub fn label(&self) -> &'static str{
+		match self{
+			PageSort::Base => "base",
+			PageSort::Name => "name",
+			PageSort::Owner => "owner",
+			PageSort::Recent => "recent",
+		}
+	}
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct PageListConfig{
+	#[serde(default)]
+	pub sort: PageSort,
+	// where the recently opened pages are remembered between sessions
+	#[serde(default = "default_recent_path")]
+	pub recent_path: String,
+}
+
+impl Default for PageListConfig{
+	fn default() -> Self {
+		Self {
+			sort: PageSort::default(),
+			recent_path: default_recent_path(),
+		}
+	}
+}
+
+fn default_recent_path() -> String {
+	"recent_pages.json".into()
+}
+
+// What the page list should show
+pub struct PageListView<'a>{
+	pub entries: Vec<PageListEntry<'a>>,
+	pub highlight_index: usize,
+	pub filter: Option<&'a str>, // the filter being typed, if there is one
+	pub sort: PageSort,
+}
+
+pub struct PageListEntry<'a>{
+	pub page: &'a UiPage,
+	pub matches: Vec<usize>, // chars of the name that match the filter
+}
+
+// Match the pattern's chars in order anywhere in the text, ignoring case.
+// Returns a score, higher for closer matches, and the positions of the matching chars.
+pub fn fuzzy_match(pattern: &str, text: &str) -> Option<(i64, Vec<usize>)>{
+	let mut pattern = pattern.chars().map(lower).peekable();
+	let mut positions = Vec::new();
+	let mut score = 0;
+	let mut prev: Option<char> = None;
+	for (i, ch) in text.chars().enumerate(){
+		let wanted = match pattern.peek(){
+			Some(wanted) => *wanted,
+			None => break,
+		};
+		if lower(ch) == wanted {
+			score += 1;
+			match positions.last(){
+				Some(last) if last + 1 == i => score += 4, // runs of matches
+				Some(last) => score -= ((i - last - 1) as i64).min(3),
+				None => score -= (i as i64).min(3),
+			}
+			// the start of a word
+			let word_start = match prev{
+				Some(prev) => !prev.is_alphanumeric() || (prev.is_lowercase() && ch.is_uppercase()),
+				None => true,
+			};
+			if word_start {
+				score += 3;
+			}
+			positions.push(i);
+			pattern.next();
+		}
+		prev = Some(ch);
+	}
+	match pattern.peek(){
+		Some(_) => None, // not every char was found
+		None => Some((score, positions)),
+	}
+}
+
+fn lower(ch: char) -> char{
+	ch.to_lowercase().next().unwrap_or(ch)
+}
+
+impl<R: Renderer> ModelProcessor<R>{
+	// the pages to show in the list, as indices into the page set with the chars matching the filter
+	pub(crate) fn visible_pages(&self) -> Vec<(usize, Vec<usize>)>{
+		let pages = self.page_set.get_page_vec();
+		let filter = match &self.page_filter{
+			Some((filter, _)) => filter.as_str(),
+			None => "",
+		};
+		let mut visible: Vec<(usize, i64, Vec<usize>)> = pages.iter().enumerate()
+			.filter_map(|(i, page)| {
+				fuzzy_match(filter, page.name()).map(|(score, matches)| (i, score, matches))
+			})
+			.collect();
+
+		match self.page_sort{
+			PageSort::Base => {},
+			PageSort::Name => visible.sort_by_key(|(i, _, _)| pages[*i].name().to_lowercase()),
+			PageSort::Owner => visible.sort_by_key(|(i, _, _)| {
+				let owner = serde_json::to_string(pages[*i].id()).unwrap_or_default();
+				(owner, pages[*i].name().to_lowercase())
+			}),
+			PageSort::Recent => visible.sort_by_key(|(i, _, _)| {
+				// pages never opened go last
+				self.recent_pages.rank(pages[*i]).unwrap_or(usize::MAX)
+			}),
+		}
+		// the best matches first, the sort is stable so ties keep the order above
+		if !filter.is_empty() {
+			visible.sort_by_key(|(_, score, _)| -score);
+		}
+		visible.into_iter().map(|(i, _, matches)| (i, matches)).collect()
+	}
+
+	pub(crate) fn page_list_view(&self) -> PageListView<'_>{
+		let pages = self.page_set.get_page_vec();
+		let visible = self.visible_pages();
+		let selected = self.page_set.selected_index();
+		let highlight_index = visible.iter().position(|(i, _)| *i == selected).unwrap_or(0);
+		PageListView {
+			entries: visible.into_iter().map(|(i, matches)| PageListEntry { page: pages[i], matches }).collect(),
+			highlight_index,
+			filter: self.page_filter.as_ref().map(|(filter, _)| filter.as_str()),
+			sort: self.page_sort,
+		}
+	}
+
+	// select the page at a position in the visible list
+	pub(crate) fn select_visible_page(&mut self, position: usize){
+		if let Some((index, _)) = self.visible_pages().get(position) {
+			self.select_page_index(*index);
+		}
+	}
+
+	// move the selection up or down the visible list
+	pub(crate) fn move_visible_selection(&mut self, up: bool){
+		let visible = self.visible_pages();
+		let selected = self.page_set.selected_index();
+		let position = match visible.iter().position(|(i, _)| *i == selected){
+			Some(position) if up => position.saturating_sub(1),
+			Some(position) => (position + 1).min(visible.len().saturating_sub(1)),
+			None => 0,
+		};
+		self.select_visible_page(position);
+	}
+
+	// open the page at a position in the visible list, if there is one
+	pub(crate) fn open_visible_page(&mut self, position: usize){
+		let index = match self.visible_pages().get(position){
+			Some((index, _)) => *index,
+			None => return,
+		};
+		self.select_page_index(index);
+		if let Some(mgr) = self.page_set.selected_page() {
+			self.recent_pages.touch(mgr.get_page());
+		}
+		self.page_filter = None;
+		self.open_tab();
+	}
+
+	// open the highlighted page
+	pub(crate) fn open_selected_page(&mut self){
+		let selected = self.page_set.selected_index();
+		if let Some(position) = self.visible_pages().iter().position(|(i, _)| *i == selected) {
+			self.open_visible_page(position);
+		}
+	}
+
+	pub(crate) fn cycle_page_sort(&mut self){
+		self.page_sort = self.page_sort.next();
+	}
+
+	pub(crate) fn start_page_filter(&mut self){
+		if self.page_filter.is_none() {
+			self.page_filter = Some((String::new(), 0));
+		}
+	}
+
+	// digits open the numbered pages, other characters go to the filter once it is started with /.
+	// Letters are left to the key bindings until then, such as q to quit
+	pub(crate) fn type_in_page_list(&mut self, ch: char){
+		if self.page_filter.is_none() {
+			if let Some(digit @ 1..=9) = ch.to_digit(10) {
+				self.open_visible_page(digit as usize - 1);
+			}
+			return;
+		}
+		self.edit_page_filter(EditOp::Insert(String::from(ch)));
+	}
+
+	// change the filter, and move the selection to the best match
+	pub(crate) fn edit_page_filter(&mut self, op: EditOp) -> bool{
+		let changed = match &mut self.page_filter{
+			Some((filter, cursor)) => line_edit::apply(filter, cursor, op),
+			None => return false,
+		};
+		if changed {
+			self.select_visible_page(0);
+		}
+		changed
+	}
+}
+
+
+
+#[cfg(test)]
+mod tests{
+    use super::fuzzy_match;
+
+    fn positions(pattern: &str, text: &str) -> Option<Vec<usize>>{
+        fuzzy_match(pattern, text).map(|(_, positions)| positions)
+    }
+
+    #[test]
+    fn matches_in_order(){
+        assert_eq!(positions("", "Alpha"), Some(vec![]));
+        assert_eq!(positions("ah", "Alpha"), Some(vec![0, 3]));
+        assert_eq!(positions("LPH", "alpha"), Some(vec![1, 2, 3]));
+        assert_eq!(positions("ha", "Alpha"), Some(vec![3, 4]));
+        assert_eq!(positions("hl", "Alpha"), None);
+        assert_eq!(positions("alphas", "Alpha"), None);
+    }
+
+    #[test]
+    fn closer_matches_score_higher(){
+        let score = |pattern, text| fuzzy_match(pattern, text).map(|(score, _)| score).unwrap();
+        // runs of chars beat scattered chars
+        assert!(score("set", "Settings") > score("set", "Sensor Status"));
+        // word starts beat the middle of words
+        assert!(score("ls", "Light Switch") > score("ls", "Tools"));
+    }
+}
@@ -2,6 +2,8 @@ use std::collections::HashMap;
 
 use spider_client::message::{UiElement, UiPageManager, UiElementKind, DatasetData, AbsoluteDatasetPath};
 
+use super::line_edit::{self, EditOp};
+
 
 
 
@@ -12,6 +14,13 @@ pub struct PageState{
     selected_datum: Option<DatasetData>,
 
     uncommited_inputs: HashMap<(String, Vec<usize>), String>, // Map from element id to contents
+    input_cursors: HashMap<(String, Vec<usize>), usize>, // Map from element id to cursor position in chars
+
+    // viewport
+    scroll: u16, // first row of the page that is visible
+    viewport_height: u16,
+    content_height: u16,
+    follow_selection: bool, // scroll to the selected element on the next render
 }
 
 impl Default for PageState{
@@ -21,7 +30,13 @@ impl Default for PageState{
             selected_datasets: Default::default(),
             selected_datum: None,
 
-            uncommited_inputs: Default::default()
+            uncommited_inputs: Default::default(),
+            input_cursors: Default::default(),
+
+            scroll: 0,
+            viewport_height: 0,
+            content_height: 0,
+            follow_selection: false,
         }
     }
 }
@@ -39,6 +54,7 @@ impl PageState{
     pub fn clear_uncommitted_input(&mut self, id: &String, dataset_indices: &Vec<usize>){
         let key = (id.clone(), dataset_indices.clone());
         self.uncommited_inputs.remove(&key);
+        self.input_cursors.remove(&key);
     }
     pub fn set_uncommited_input(&mut self, id: String, dataset_indices: &Vec<usize>, value: String){
         let key = (id.clone(), dataset_indices.clone());
@@ -57,6 +73,7 @@ impl PageState{
         match &self.selected{
             Some(id) => {
                 let key = (id.clone(), self.selected_datasets.clone());
+                self.input_cursors.remove(&key);
                 self.uncommited_inputs.remove(&key)
             },
             None => None,
@@ -72,6 +89,78 @@ impl PageState{
         };
     }
 
+    // inputs that have been typed but not sent
+    pub fn pending_input_count(&self) -> usize{
+        self.uncommited_inputs.values().filter(|text| !text.is_empty()).count()
+    }
+
+    // input cursor, defaults to the end of the input
+    pub fn get_input_cursor(&self, id: &String, dataset_indices: &Vec<usize>) -> usize{
+        let key = (id.clone(), dataset_indices.clone());
+        let len = self.uncommited_inputs.get(&key).map(|text| text.chars().count()).unwrap_or(0);
+        match self.input_cursors.get(&key) {
+            Some(cursor) => (*cursor).min(len),
+            None => len,
+        }
+    }
+    // edit the selected input at its cursor, returns false if the edit had no effect
+    pub fn edit_selected_input(&mut self, op: EditOp) -> bool{
+        let key = match &self.selected{
+            Some(id) => (id.clone(), self.selected_datasets.clone()),
+            None => return false,
+        };
+        let text = self.uncommited_inputs.entry(key.clone()).or_default();
+        let cursor = self.input_cursors.entry(key.clone()).or_insert(text.chars().count());
+        let changed = line_edit::apply(text, cursor, op);
+        if text.is_empty() {
+            self.uncommited_inputs.remove(&key);
+            self.input_cursors.remove(&key);
+        }
+        changed
+    }
+
+    // viewport
+    pub fn get_scroll(&self) -> u16{
+        self.scroll
+    }
+    // called by the renderer with the size of the page and the space it has to draw it
+    pub fn set_viewport(&mut self, viewport_height: u16, content_height: u16){
+        self.viewport_height = viewport_height;
+        self.content_height = content_height;
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+    fn max_scroll(&self) -> u16{
+        self.content_height.saturating_sub(self.viewport_height)
+    }
+    pub fn scroll_by(&mut self, rows: i32){
+        let scroll = (self.scroll as i32 + rows).clamp(0, self.max_scroll() as i32);
+        self.scroll = scroll as u16;
+    }
+    pub fn scroll_page_up(&mut self){
+        self.scroll_by(-(self.viewport_height.max(1) as i32));
+    }
+    pub fn scroll_page_down(&mut self){
+        self.scroll_by(self.viewport_height.max(1) as i32);
+    }
+    pub fn scroll_to_top(&mut self){
+        self.scroll = 0;
+    }
+    pub fn scroll_to_bottom(&mut self){
+        self.scroll = self.max_scroll();
+    }
+    // scroll the least amount needed to show the rows from top to bottom
+    pub fn scroll_to_show(&mut self, top: u16, bottom: u16){
+        if top < self.scroll {
+            self.scroll = top;
+        }else if bottom > self.scroll.saturating_add(self.viewport_height) {
+            self.scroll = bottom.saturating_sub(self.viewport_height).min(top);
+        }
+        self.scroll = self.scroll.min(self.max_scroll());
+    }
+    pub fn take_follow_selection(&mut self) -> bool{
+        std::mem::replace(&mut self.follow_selection, false)
+    }
+
     // Selected element management
     pub fn get_selected_id(&self) -> Option<&String>{
         self.selected.as_ref()
@@ -82,8 +171,14 @@ impl PageState{
     pub fn get_selected_datum(&self) -> &Option<DatasetData>{
         &self.selected_datum
     }
+    pub fn select(&mut self, id: String, dataset_indices: Vec<usize>, datum: Option<DatasetData>){
+        self.selected = Some(id);
+        self.selected_datasets = dataset_indices;
+        self.selected_datum = datum;
+    }
 
     pub fn select_next(&mut self, mgr: &UiPageManager, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, direction: SelectDirection) {
+        self.follow_selection = true;
         let path = self.get_selected_id().and_then(|s| mgr.get_path(s));
         let mut selected_path = match path{
             // get selected path (string id -> get path from page)
@@ -257,7 +352,26 @@ fn elem_select_next(elem: &UiElement, index: usize, data: &Option<&DatasetData>,
                 SelectDirection::Right => None,
             }
         },
-        UiElementKind::Grid(_, _) => todo!(),
+        UiElementKind::Grid(columns, rows) => {
+            let children: Vec<_> = elem.children_dataset(data, data_map).collect();
+            let (columns, _) = grid_dimensions(*columns, *rows, children.len());
+            let row = index / columns;
+            let column = index % columns;
+            // cells in the direction of travel, nearest first. Leaving the edge of the grid falls back to the parent
+            let candidates: Vec<usize> = match direction{
+                SelectDirection::Up => (0..row).rev().map(|r| r * columns + column).collect(),
+                SelectDirection::Down => (row+1..).map(|r| r * columns + column).take_while(|i| *i < children.len()).collect(),
+                SelectDirection::Left => (0..column).rev().map(|c| row * columns + c).collect(),
+                SelectDirection::Right => (column+1..columns).map(|c| row * columns + c).filter(|i| *i < children.len()).collect(),
+            };
+            for i in candidates{
+                let (dataset_index, child, datum) = &children[i];
+                if let Some(res) = elem_select_enter_towards(child, datum, data_map, direction){
+                    return Some(prepend_dataset_index(*dataset_index, res));
+                }
+            }
+            None
+        },
         _ => None,
     }
 }
@@ -340,7 +454,26 @@ fn elem_select_enter_towards(elem: &UiElement, data: &Option<&DatasetData>, data
                 },
             }
         },
-        UiElementKind::Grid(_, _) => todo!(),
+        UiElementKind::Grid(_, _) => {
+            match direction{
+                SelectDirection::Up | SelectDirection::Left => { // reverse order
+                    for (i, child, datum) in elem.children_dataset(data, data_map).rev(){
+                        if let Some(res) = elem_select_enter_towards(child, &datum, data_map, direction){
+                            return Some(prepend_dataset_index(i, res));
+                        }
+                    }
+                    None
+                },
+                _ => { // get first valid
+                    for (i, child, datum) in elem.children_dataset(data, data_map){
+                        if let Some(res) = elem_select_enter_towards(child, &datum, data_map, direction){
+                            return Some(prepend_dataset_index(i, res));
+                        }
+                    }
+                    None
+                },
+            }
+        },
         _ => {
             // Todo: Better logic around which kinds of elements are selectable
             let not_none = elem.kind().clone().resolve(data) != UiElementKind::None;
@@ -362,6 +495,33 @@ fn elem_select_enter_towards(elem: &UiElement, data: &Option<&DatasetData>, data
     }
 }
 
+// need to prepend to the data index tail if the parent node had a dataset
+fn prepend_dataset_index(dataset_index: Option<usize>, selection: (String, Vec<usize>, Option<DatasetData>)) -> (String, Vec<usize>, Option<DatasetData>){
+    let (id, mut dataset_tail, selected_datum) = selection;
+    match dataset_index{
+        Some(dataset_index) => {
+            let mut tail = vec![dataset_index];
+            tail.append(&mut dataset_tail);
+            (id, tail, selected_datum)
+        },
+        None => (id, dataset_tail, selected_datum),
+    }
+}
+
+// Grid(columns, rows) lays children out row by row. A zero dimension is derived
+// from the number of children, and extra children add rows.
+pub fn grid_dimensions(columns: usize, rows: usize, count: usize) -> (usize, usize){
+    let columns = if columns > 0 {
+        columns
+    }else if rows > 0 {
+        (count + rows - 1) / rows
+    }else{
+        count
+    }.max(1);
+    let rows = ((count + columns - 1) / columns).max(rows);
+    (columns, rows)
+}
+
 
 #[derive(Debug, Clone, Copy)]
 pub enum SelectDirection{
@@ -371,3 +531,91 @@ pub enum SelectDirection{
 	Right,
 }
 
+
+
+#[cfg(test)]
+mod tests{
+    use std::collections::HashMap;
+
+    use spider_client::message::UiElementKind;
+
+    use crate::test_util::{button, page, text, text_entry};
+
+    use super::{PageState, SelectDirection};
+
+    fn selected(state: &PageState) -> Option<&str>{
+        state.get_selected_id().map(|id| id.as_str())
+    }
+
+    #[test]
+    fn rows_navigation(){
+        let mgr = page("Test Page", UiElementKind::Rows, vec![
+            text("Hello"),
+            button("ok", "Ok"),
+            text_entry("name", "Name"),
+        ]);
+        let data_map = HashMap::new();
+        let mut state = PageState::default();
+
+        // text is not selectable, so the first selection skips it
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        assert_eq!(selected(&state), Some("ok"));
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        assert_eq!(selected(&state), Some("name"));
+        // nothing further down, selection stays
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        assert_eq!(selected(&state), Some("name"));
+        // rows ignore sideways movement
+        state.select_next(&mgr, &data_map, SelectDirection::Left);
+        assert_eq!(selected(&state), Some("name"));
+        state.select_next(&mgr, &data_map, SelectDirection::Up);
+        assert_eq!(selected(&state), Some("ok"));
+        state.select_next(&mgr, &data_map, SelectDirection::Up);
+        assert_eq!(selected(&state), Some("ok"));
+    }
+
+    #[test]
+    fn columns_navigation(){
+        let mgr = page("Test Page", UiElementKind::Columns, vec![
+            button("left", "Left"),
+            button("right", "Right"),
+        ]);
+        let data_map = HashMap::new();
+        let mut state = PageState::default();
+
+        state.select_next(&mgr, &data_map, SelectDirection::Right);
+        assert_eq!(selected(&state), Some("left"));
+        state.select_next(&mgr, &data_map, SelectDirection::Right);
+        assert_eq!(selected(&state), Some("right"));
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        assert_eq!(selected(&state), Some("right"));
+        state.select_next(&mgr, &data_map, SelectDirection::Left);
+        assert_eq!(selected(&state), Some("left"));
+    }
+
+    #[test]
+    fn grid_navigation(){
+        let mgr = page("Test Page", UiElementKind::Grid(2, 2), vec![
+            button("a", "A"),
+            button("b", "B"),
+            button("c", "C"),
+            button("d", "D"),
+        ]);
+        let data_map = HashMap::new();
+        let mut state = PageState::default();
+
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        assert_eq!(selected(&state), Some("a"));
+        state.select_next(&mgr, &data_map, SelectDirection::Right);
+        assert_eq!(selected(&state), Some("b"));
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        assert_eq!(selected(&state), Some("d"));
+        state.select_next(&mgr, &data_map, SelectDirection::Left);
+        assert_eq!(selected(&state), Some("c"));
+        state.select_next(&mgr, &data_map, SelectDirection::Up);
+        assert_eq!(selected(&state), Some("a"));
+        // at the edge of the grid, selection stays
+        state.select_next(&mgr, &data_map, SelectDirection::Up);
+        assert_eq!(selected(&state), Some("a"));
+    }
+}
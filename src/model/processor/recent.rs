@@ -0,0 +1,111 @@
+// Pages the user has opened recently, most recent first, and the tabs they left open.
+// Both are kept per profile across sessions.
+
+use std::{collections::HashMap, fs, path::PathBuf};
+
+use serde::{Serialize, Deserialize};
+use spider_client::message::UiPage;
+use tracing::warn;
+
+
+
+const MAX_RECENT: usize = 50;
+
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+struct ProfilePages{
+    #[serde(default)]
+    recent: Vec<String>, // page keys
+    #[serde(default)]
+    tabs: Vec<String>,
+    #[serde(default)]
+    active_tab: usize,
+}
+
+pub struct RecentPages{
+    path: Option<PathBuf>, // none keeps them only in memory
+    profile: String,
+    profiles: HashMap<String, ProfilePages>,
+}
+
+impl RecentPages{
+    pub fn load(path: PathBuf, profile: &str) -> Self{
+        let profiles = match fs::read_to_string(&path){
+            Ok(data) => serde_json::from_str(&data).unwrap_or_else(|e| {
+                warn!("Ignoring unreadable recent pages file {:?}: {}", path, e);
+                HashMap::new()
+            }),
+            Err(_) => HashMap::new(),
+        };
+        Self {
+            path: Some(path),
+            profile: profile.to_string(),
+            profiles,
+        }
+    }
+
+    // starting with the given tabs and saving nothing, such as for a replayed session
+    pub fn in_memory(profile: &str, tabs: Vec<String>, active_tab: usize) -> Self{
+        let pages = ProfilePages {
+            tabs,
+            active_tab,
+            ..Default::default()
+        };
+        Self {
+            path: None,
+            profile: profile.to_string(),
+            profiles: HashMap::from([(profile.to_string(), pages)]),
+        }
+    }
+
+    // pages are told apart by their owner and name
+    pub fn key(page: &UiPage) -> String{
+        let owner = serde_json::to_string(page.id()).unwrap_or_default();
+        format!("{}/{}", owner, page.name())
+    }
+
+    // how recently the page was opened, 0 is the most recent
+    pub fn rank(&self, page: &UiPage) -> Option<usize>{
+        let key = Self::key(page);
+        self.profiles.get(&self.profile)?.recent.iter().position(|k| *k == key)
+    }
+
+    pub fn touch(&mut self, page: &UiPage){
+        let key = Self::key(page);
+        let recent = &mut self.profiles.entry(self.profile.clone()).or_default().recent;
+        recent.retain(|k| *k != key);
+        recent.insert(0, key);
+        recent.truncate(MAX_RECENT);
+        self.save();
+    }
+
+    // the tabs open at the end of the last session, and which was active
+    pub fn tabs(&self) -> (Vec<String>, usize){
+        match self.profiles.get(&self.profile){
+            Some(pages) => (pages.tabs.clone(), pages.active_tab),
+            None => (Vec::new(), 0),
+        }
+    }
+
+    pub fn set_tabs(&mut self, tabs: Vec<String>, active_tab: usize){
+        let pages = self.profiles.entry(self.profile.clone()).or_default();
+        if pages.tabs == tabs && pages.active_tab == active_tab {
+            return;
+        }
+        pages.tabs = tabs;
+        pages.active_tab = active_tab;
+        self.save();
+    }
+
+    fn save(&self){
+        let path = match &self.path{
+            Some(path) => path,
+            None => return,
+        };
+        let result = serde_json::to_string(&self.profiles)
+            .map_err(|e| e.to_string())
+            .and_then(|data| fs::write(path, data).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to save recent pages to {:?}: {}", path, e);
+        }
+    }
+}
@@ -0,0 +1,143 @@
+// Two pages on screen at once, side by side or one above the other. The first pane shows the
+// active tab, the second shows a page of its own with its own state. Both share the datasets.
+
+use spider_client::SpiderId2048;
+
+use crate::renderer::{Renderer, HitMap, HitTarget};
+
+use super::{ModelProcessor, ModelView, PageState};
+
+
+
+const MIN_RATIO: u16 = 20;
+const MAX_RATIO: u16 = 80;
+const RATIO_STEP: u16 = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitDirection{
+	SideBySide,
+	Stacked,
+}
+
+// How the renderer should lay out the panes
+#[derive(Debug, Clone)]
+pub struct SplitLayout{
+	pub direction: SplitDirection,
+	pub ratio: u16, // percent of the space given to the first pane
+	pub focus: usize, // the pane that keys go to
+}
+
+pub(crate) struct Split{
+	layout: SplitLayout,
+	page: SpiderId2048, // shown in the second pane
+	state: PageState,
+}
+
+impl Split{
+	pub(crate) fn state(&self) -> &PageState{
+		&self.state
+	}
+
+	pub(crate) fn state_mut(&mut self) -> &mut PageState{
+		&mut self.state
+	}
+}
+
+impl<R: Renderer> ModelProcessor<R>{
+	// split the page view, or change the direction of an existing split
+	pub(crate) fn split(&mut self, direction: SplitDirection){
+		if !matches!(self.view, ModelView::Page) {
+			return;
+		}
+		if let Some(split) = &mut self.split {
+			split.layout.direction = direction;
+			return;
+		}
+		// the second pane starts on the next tab, or the same page if there is only one
+		let next = self.tabs.get((self.active_tab + 1) % self.tabs.len().max(1)).cloned();
+		let page = match next.or_else(|| self.get_current_page().map(|page| page.id().clone())){
+			Some(page) => page,
+			None => return,
+		};
+		self.split = Some(Split {
+			layout: SplitLayout { direction, ratio: 50, focus: 1 },
+			page,
+			state: PageState::default(),
+		});
+	}
+
+	pub(crate) fn unsplit(&mut self){
+		self.split = None;
+	}
+
+	pub(crate) fn focus_next_pane(&mut self){
+		if let Some(split) = &mut self.split {
+			split.layout.focus = 1 - split.layout.focus;
+		}
+	}
+
+	// give more or less of the screen to the focused pane
+	pub(crate) fn resize_pane(&mut self, grow: bool){
+		if let Some(split) = &mut self.split {
+			let layout = &mut split.layout;
+			let ratio = if grow == (layout.focus == 0) {
+				layout.ratio + RATIO_STEP
+			}else{
+				layout.ratio.saturating_sub(RATIO_STEP)
+			};
+			layout.ratio = ratio.clamp(MIN_RATIO, MAX_RATIO);
+		}
+	}
+
+	// focus the pane under a screen position, if there is one
+	pub(crate) fn focus_pane_at(&mut self, x: u16, y: u16){
+		let pane = self.hit_map.hits(x, y).find_map(|target| match target{
+			HitTarget::Pane(pane) => Some(*pane),
+			_ => None,
+		});
+		if let (Some(split), Some(pane)) = (&mut self.split, pane) {
+			split.layout.focus = pane;
+		}
+	}
+
+	// the page in the second pane, if that is where keys go
+	pub(crate) fn focused_split_page(&self) -> Option<SpiderId2048>{
+		if !matches!(self.view, ModelView::Page) {
+			return None;
+		}
+		match &self.split{
+			Some(split) if split.layout.focus == 1 => Some(split.page.clone()),
+			_ => None,
+		}
+	}
+
+	// show a different page in the second pane
+	pub(crate) fn set_split_page(&mut self, page: SpiderId2048){
+		if let Some(split) = &mut self.split {
+			if split.page != page {
+				split.page = page;
+				split.state = PageState::default();
+			}
+		}
+	}
+
+	// close the split if its page is gone
+	pub(crate) fn sync_split(&mut self){
+		let gone = match &self.split{
+			Some(split) => !self.page_set.get_page_vec().iter().any(|page| *page.id() == split.page),
+			None => false,
+		};
+		if gone {
+			self.split = None;
+		}
+	}
+
+	pub(crate) fn render_split(&mut self, renderer: &mut R) -> Option<HitMap>{
+		let split = self.split.as_mut()?;
+		let main = self.page_set.selected_page()?;
+		let other = self.page_set.get_page(&split.page)?;
+		let main_state = self.page_states.entry(main.get_page().id().clone()).or_default();
+		let panes = [(main.get_page(), main_state), (other.get_page(), &mut split.state)];
+		Some(renderer.render_split(panes, &split.layout, &self.datasets))
+	}
+}
@@ -2,7 +2,7 @@
 
 use std::collections::HashMap;
 
-use spider_client::message::{UiPage, UiPageManager, AbsoluteDatasetPath, DatasetData};
+use spider_client::{message::{UiPage, UiPageManager, AbsoluteDatasetPath, DatasetData, UiElementKind}, SpiderId2048};
 
 use crate::renderer::Renderer;
 
@@ -15,10 +15,21 @@ use super::{ModelProcessor, PageState};
 impl<R: Renderer> ModelProcessor<R>{
 
 	pub(crate) fn set_pages(&mut self, pages: Vec<UiPage>){
+		// drop the state of pages that no longer exist
+		self.page_states.retain(|id, _| pages.iter().any(|page| page.id() == id));
 		self.page_set.clear();
 		self.page_set.add_pages(pages)
 	}
 
+	// drop a page and its state
+	pub(crate) fn remove_page(&mut self, id: &SpiderId2048){
+		let pages = self.page_set.get_page_vec().into_iter()
+			.filter(|page| page.id() != id)
+			.cloned()
+			.collect();
+		self.set_pages(pages);
+	}
+
 	pub(crate) fn upsert_page(&mut self, page: UiPage){
 		self.page_set.upsert_page(page);
 	}
@@ -32,6 +43,10 @@ impl<R: Renderer> ModelProcessor<R>{
 	}
 
 	pub(crate) fn get_current_mgr_state(&mut self) -> Option<(&UiPageManager, &PageState)>{
+		if let Some(id) = self.focused_split_page() {
+			let mgr = self.page_set.get_page(&id)?;
+			return Some((mgr, self.split.as_ref()?.state()));
+		}
 		match self.page_set.selected_page() {
 			Some(mgr) => {
 				let id = mgr.get_page().id();
@@ -46,6 +61,10 @@ impl<R: Renderer> ModelProcessor<R>{
 	}
 
 	pub(crate) fn get_current_mgr_state_mut(&mut self) -> Option<(&mut UiPageManager, &mut PageState)>{
+		if let Some(id) = self.focused_split_page() {
+			let mgr = self.page_set.get_page_mut(&id)?;
+			return Some((mgr, self.split.as_mut()?.state_mut()));
+		}
 		match self.page_set.selected_page_mut() {
 			Some(mgr) => {
 				let id = mgr.get_page().id();
@@ -59,6 +78,14 @@ impl<R: Renderer> ModelProcessor<R>{
 		}
 	}
 
+	// the kind of the selected element, resolved against its datum
+	pub(crate) fn get_selected_kind(&mut self) -> Option<UiElementKind>{
+		let (mgr, state) = self.get_current_mgr_state()?;
+		let id = state.get_selected_id()?;
+		let elem = mgr.get_by_id(id)?;
+		Some(elem.kind().clone().resolve(&state.get_selected_datum().as_ref()))
+	}
+
 	pub(crate) fn select_prev_page(&mut self){
 		self.page_set.select_prev_page()
 	}
@@ -67,9 +94,27 @@ impl<R: Renderer> ModelProcessor<R>{
 		self.page_set.select_next_page()
 	}
 
+	pub(crate) fn select_page_index(&mut self, index: usize){
+		// the page list only moves one step at a time
+		for _ in 0..self.page_set.get_page_vec().len(){
+			let current = self.page_set.selected_index();
+			if current == index {
+				break;
+			}else if current < index {
+				self.select_next_page();
+			}else{
+				self.select_prev_page();
+			}
+		}
+	}
+
 
 
 	pub(crate) fn get_context(&mut self) -> Option<(&UiPageManager, &mut PageState, &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>)>{
+		if let Some(id) = self.focused_split_page() {
+			let mgr = self.page_set.get_page(&id)?;
+			return Some((mgr, self.split.as_mut()?.state_mut(), &self.datasets));
+		}
 		match self.page_set.selected_page() {
 			Some(mgr) => {
 				let id = mgr.get_page().id();
@@ -0,0 +1,150 @@
+// The status bar along the bottom of a page, and which items it shows.
+
+use std::{collections::hash_map::DefaultHasher, hash::{Hash, Hasher}};
+
+use serde::{Serialize, Deserialize};
+use spider_client::{message::UiElementKind, SpiderId2048};
+
+use crate::{renderer::Renderer, keymap::Action, model::update::Status};
+
+use super::{ModelProcessor, ModelView};
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum StatusItem{
+	Connection,
+	Base,
+	Identity,
+	Page,
+	Owner,
+	PendingInputs,
+	LastUpdate,
+	KeyHints,
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct StatusBarConfig{
+	#[serde(default = "default_items")]
+	pub items: Vec<StatusItem>,
+	// show the selected element's id and dataset indices above the status bar
+	#[serde(default)]
+	pub debug: bool,
+}
+
+impl Default for StatusBarConfig{
+	fn default() -> Self {
+		Self {
+			items: default_items(),
+			debug: false,
+		}
+	}
+}
+
+fn default_items() -> Vec<StatusItem>{
+	vec![
+		StatusItem::Connection,
+		StatusItem::Base,
+		StatusItem::Identity,
+		StatusItem::Page,
+		StatusItem::Owner,
+		StatusItem::PendingInputs,
+		StatusItem::LastUpdate,
+		StatusItem::KeyHints,
+	]
+}
+
+impl<R: Renderer> ModelProcessor<R>{
+	pub(crate) fn shows_status_item(&self, item: StatusItem) -> bool{
+		self.config.status_bar.items.contains(&item)
+	}
+
+	pub(crate) fn status(&mut self) -> Status{
+		let mut status = Status{
+			connection: self.connection.clone(),
+			..Default::default()
+		};
+		let items = self.config.status_bar.items.clone();
+		for item in items{
+			match item{
+				StatusItem::Connection => status.segments.push(self.connection.to_string()),
+				StatusItem::Base => {
+					match &self.base_address{
+						Some(addr) => status.segments.push(format!("{} ({})", self.profile_name, addr)),
+						None => status.segments.push(self.profile_name.clone()),
+					}
+				},
+				StatusItem::Identity => status.segments.push(format!("as {}", self.identity)),
+				StatusItem::Page => {
+					if let Some(page) = self.get_current_page() {
+						status.segments.push(page.name().clone());
+					}
+				},
+				StatusItem::Owner => {
+					if let Some(page) = self.get_current_page() {
+						status.segments.push(format!("owner {}", short_id(page.id())));
+					}
+				},
+				StatusItem::PendingInputs => {
+					let pending = match self.get_current_mgr_state(){
+						Some((_, state)) => state.pending_input_count(),
+						None => 0,
+					};
+					if pending > 0 {
+						status.segments.push(format!("{} unsent", pending));
+					}
+				},
+				StatusItem::LastUpdate => {
+					if let Some(time) = self.last_update {
+						status.segments.push(format!("updated {}", time.format("%H:%M:%S")));
+					}
+				},
+				StatusItem::KeyHints => status.hints = self.key_hints(),
+			}
+		}
+
+		if self.debug {
+			if let Some((_, state)) = self.get_current_mgr_state() {
+				let default = String::from("-");
+				let id_text = state.get_selected_id().unwrap_or(&default);
+				status.debug = Some(format!("{} | {:?}", id_text, state.get_selected_datasets()));
+			}
+		}
+		status
+	}
+
+	// keys that do something useful with the selected element
+	fn key_hints(&mut self) -> Vec<(String, String)>{
+		let mut actions = match self.view{
+			ModelView::Page => {
+				match self.get_selected_kind(){
+					Some(UiElementKind::TextEntry) => vec![(Action::Submit, "send"), (Action::DeleteBack, "erase")],
+					Some(UiElementKind::Button) => vec![(Action::Submit, "press")],
+					_ => vec![],
+				}
+			},
+			ModelView::List => vec![(Action::Submit, "open"), (Action::Filter, "filter"), (Action::CycleSort, "sort")],
+			ModelView::Menu => vec![(Action::Submit, "open")],
+			ModelView::Datasets => vec![(Action::Submit, "expand")],
+			ModelView::Traffic => vec![(Action::Filter, "filter"), (Action::TogglePause, "pause"), (Action::Export, "export")],
+		};
+		actions.push((Action::OpenMenu, "menu"));
+		let mode = self.key_mode();
+		actions.into_iter()
+			.filter_map(|(action, description)| {
+				self.keymap.key_for(mode, action).map(|key| (key, String::from(description)))
+			})
+			.collect()
+	}
+}
+
+// a short fingerprint of an id, the full id is too long to show
+fn short_id(id: &SpiderId2048) -> String{
+	let mut hasher = DefaultHasher::new();
+	match serde_json::to_string(id){
+		Ok(text) => text.hash(&mut hasher),
+		Err(_) => return String::from("?"),
+	}
+	format!("{:08x}", hasher.finish() as u32)
+}
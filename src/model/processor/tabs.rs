@@ -0,0 +1,148 @@
+// Pages kept open as tabs. The selected page in the page set is always the active tab while
+// a page is being viewed, and each tab keeps its own page state.
+
+use spider_client::SpiderId2048;
+
+use crate::renderer::Renderer;
+
+use super::{ModelProcessor, ModelView, recent::RecentPages};
+
+
+
+impl<R: Renderer> ModelProcessor<R>{
+	// open the selected page in a tab, or switch to its tab if it is already open
+	pub(crate) fn open_tab(&mut self){
+		let id = match self.get_current_page(){
+			Some(page) => page.id().clone(),
+			None => return,
+		};
+		self.active_tab = match self.tabs.iter().position(|tab| *tab == id){
+			Some(index) => index,
+			None => {
+				self.tabs.push(id);
+				self.tabs.len() - 1
+			},
+		};
+		self.view = ModelView::Page;
+		self.save_tabs();
+	}
+
+	pub(crate) fn select_tab(&mut self, index: usize){
+		let page_index = match self.tabs.get(index).and_then(|id| self.page_index(id)){
+			Some(page_index) => page_index,
+			None => return,
+		};
+		// the second pane of a split picks its page from the tabs too
+		if self.focused_split_page().is_some() {
+			let id = self.tabs[index].clone();
+			self.set_split_page(id);
+			return;
+		}
+		self.select_page_index(page_index);
+		self.active_tab = index;
+		self.view = ModelView::Page;
+		self.save_tabs();
+	}
+
+	// move to the next or previous tab, wrapping around at the ends
+	pub(crate) fn cycle_tab(&mut self, forward: bool){
+		let count = self.tabs.len();
+		if count == 0 {
+			return;
+		}
+		let current = match self.focused_split_page(){
+			Some(id) => self.tabs.iter().position(|tab| *tab == id).unwrap_or(0),
+			None => self.active_tab,
+		};
+		let index = if forward {
+			(current + 1) % count
+		}else{
+			(current + count - 1) % count
+		};
+		self.select_tab(index);
+	}
+
+	pub(crate) fn close_tab(&mut self){
+		if !matches!(self.view, ModelView::Page) || self.tabs.is_empty() {
+			return;
+		}
+		// in the second pane of a split, close the pane instead
+		if self.focused_split_page().is_some() {
+			self.unsplit();
+			return;
+		}
+		self.tabs.remove(self.active_tab.min(self.tabs.len() - 1));
+		if self.tabs.is_empty() {
+			self.active_tab = 0;
+			self.view = ModelView::List;
+		}else{
+			self.select_tab(self.active_tab.min(self.tabs.len() - 1));
+		}
+		self.save_tabs();
+	}
+
+	pub(crate) fn tab_names(&self) -> Vec<String>{
+		let pages = self.page_set.get_page_vec();
+		self.tabs.iter()
+			.filter_map(|id| pages.iter().find(|page| page.id() == id))
+			.map(|page| page.name().clone())
+			.collect()
+	}
+
+	// bring the tabs up to date with the pages, after the base sends them
+	pub(crate) fn sync_tabs(&mut self){
+		// reopen the tabs from the last session once there are pages
+		let restoring = !self.restore_tabs.is_empty() && !self.page_set.get_page_vec().is_empty();
+		if restoring {
+			let pages = self.page_set.get_page_vec();
+			for key in self.restore_tabs.iter(){
+				if let Some(page) = pages.iter().find(|page| RecentPages::key(page) == *key) {
+					if !self.tabs.contains(page.id()) {
+						self.tabs.push(page.id().clone());
+					}
+					if self.restore_active.as_ref() == Some(key) {
+						self.active_tab = self.tabs.len() - 1;
+					}
+				}
+			}
+			self.restore_tabs.clear();
+		}
+
+		// close the tabs of pages that are gone
+		let active = self.tabs.get(self.active_tab).cloned();
+		let pages = self.page_set.get_page_vec();
+		self.tabs.retain(|id| pages.iter().any(|page| page.id() == id));
+		self.active_tab = match active.and_then(|id| self.tabs.iter().position(|tab| *tab == id)){
+			Some(index) => index,
+			None => self.active_tab.min(self.tabs.len().saturating_sub(1)),
+		};
+
+		// the page set loses its selection when the pages are replaced
+		let reopen = restoring && matches!(self.view, ModelView::List);
+		if matches!(self.view, ModelView::Page) || reopen {
+			if self.tabs.is_empty() {
+				self.view = ModelView::List;
+			}else{
+				self.select_tab(self.active_tab);
+			}
+		}
+		self.sync_split();
+		// keep the saved tabs until they have had a chance to be restored
+		if self.restore_tabs.is_empty() {
+			self.save_tabs();
+		}
+	}
+
+	fn page_index(&self, id: &SpiderId2048) -> Option<usize>{
+		self.page_set.get_page_vec().iter().position(|page| page.id() == id)
+	}
+
+	fn save_tabs(&mut self){
+		let pages = self.page_set.get_page_vec();
+		let keys = self.tabs.iter()
+			.filter_map(|id| pages.iter().find(|page| page.id() == id))
+			.map(|page| RecentPages::key(page))
+			.collect();
+		self.recent_pages.set_tabs(keys, self.active_tab);
+	}
+}
@@ -0,0 +1,240 @@
+// A record of the messages passed between the base and the model, for debugging peripherals.
+// The most recent messages are kept, and can be filtered, paused and exported as JSON lines.
+
+use std::{collections::VecDeque, fs::File, io::{self, BufWriter, Write}};
+
+use chrono::{DateTime, Local};
+use serde::{Serialize, Deserialize};
+use serde_json::{json, Value};
+
+use crate::{renderer::Renderer, model::update::{TrafficDirection, TrafficEntry}};
+
+use super::{ModelProcessor, line_edit::{self, EditOp}};
+
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct TrafficConfig{
+	// how many messages are kept
+	#[serde(default = "default_capacity")]
+	pub capacity: usize,
+	#[serde(default = "default_export_path")]
+	pub export_path: String,
+}
+
+impl Default for TrafficConfig{
+	fn default() -> Self {
+		Self {
+			capacity: default_capacity(),
+			export_path: default_export_path(),
+		}
+	}
+}
+
+fn default_capacity() -> usize {
+	1000
+}
+
+fn default_export_path() -> String {
+	"spider_tui_traffic.jsonl".into()
+}
+
+// What the traffic monitor should show
+pub struct TrafficView<'a>{
+	pub rows: Vec<String>,
+	pub highlight_index: usize,
+	pub detail: String, // the selected message, pretty printed
+	pub filter: Option<&'a str>,
+	pub paused: bool,
+	pub dropped: usize,
+}
+
+// A message as the monitor keeps it
+pub(crate) struct TrafficRecord{
+	time: DateTime<Local>,
+	direction: TrafficDirection,
+	kind: String, // the message's variants, such as "Ui/SetPage"
+	message: Value,
+	search: String, // lowercase kind and message, for the filter
+}
+
+impl TrafficRecord{
+	fn new(entry: TrafficEntry) -> Self{
+		// messages that cannot be serialized are still recorded by their debug text
+		let message = serde_json::to_value(&entry.message).unwrap_or_else(|_| Value::String(format!("{:?}", entry.message)));
+		let kind = message_kind(&message);
+		let search = format!("{} {}", kind, message).to_lowercase();
+		Self {
+			time: entry.time,
+			direction: entry.direction,
+			kind,
+			message,
+			search,
+		}
+	}
+}
+
+#[derive(Default)]
+pub(crate) struct TrafficMonitor{
+	entries: VecDeque<TrafficRecord>,
+	selected: Option<usize>, // position in the filtered entries, none follows the newest
+	filter: Option<(String, usize)>, // the filter being typed, and its cursor
+	paused: bool,
+	dropped: usize, // messages missed while paused
+}
+
+impl TrafficMonitor{
+	// the entries that match the filter, by kind or anywhere in the message such as a page id
+	fn visible(&self) -> Vec<&TrafficRecord>{
+		let filter = match &self.filter{
+			Some((filter, _)) if !filter.is_empty() => filter.to_lowercase(),
+			_ => return self.entries.iter().collect(),
+		};
+		self.entries.iter()
+			.filter(|entry| entry.search.contains(&filter))
+			.collect()
+	}
+}
+
+impl<R: Renderer> ModelProcessor<R>{
+	pub(crate) fn record_traffic(&mut self, entry: TrafficEntry){
+		let monitor = &mut self.traffic;
+		if monitor.paused {
+			monitor.dropped += 1;
+			return;
+		}
+		if monitor.entries.len() >= self.config.traffic.capacity.max(1) {
+			monitor.entries.pop_front();
+			// keep the selection on the same message
+			if let Some(selected) = &mut monitor.selected {
+				*selected = selected.saturating_sub(1);
+			}
+		}
+		monitor.entries.push_back(TrafficRecord::new(entry));
+	}
+
+	pub(crate) fn traffic_view(&self) -> TrafficView<'_>{
+		let monitor = &self.traffic;
+		let visible = monitor.visible();
+		let highlight_index = self.traffic_selected(visible.len());
+		let rows = visible.iter()
+			.map(|entry| format!("{} {:<3} {}", entry.time.format("%H:%M:%S%.3f"), entry.direction, entry.kind))
+			.collect();
+		let detail = match visible.get(highlight_index){
+			Some(entry) => serde_json::to_string_pretty(&entry.message).unwrap_or_default(),
+			None => String::from("No messages"),
+		};
+		TrafficView {
+			rows,
+			highlight_index,
+			detail,
+			filter: monitor.filter.as_ref().map(|(filter, _)| filter.as_str()),
+			paused: monitor.paused,
+			dropped: monitor.dropped,
+		}
+	}
+
+	fn traffic_selected(&self, count: usize) -> usize{
+		match self.traffic.selected{
+			Some(selected) => selected.min(count.saturating_sub(1)),
+			None => count.saturating_sub(1),
+		}
+	}
+
+	// move the selection, following the newest message again at the bottom
+	pub(crate) fn move_traffic_selection(&mut self, offset: isize){
+		let count = self.traffic.visible().len();
+		let selected = self.traffic_selected(count) as isize + offset;
+		self.select_traffic_row(selected.max(0) as usize);
+	}
+
+	pub(crate) fn select_traffic_row(&mut self, index: usize){
+		let count = self.traffic.visible().len();
+		self.traffic.selected = if index + 1 >= count { None } else { Some(index) };
+	}
+
+	pub(crate) fn toggle_traffic_pause(&mut self){
+		let monitor = &mut self.traffic;
+		monitor.paused = !monitor.paused;
+		if !monitor.paused && monitor.dropped > 0 {
+			let dropped = std::mem::take(&mut monitor.dropped);
+			self.add_notice(format!("Missed {} messages while paused", dropped));
+		}
+	}
+
+	pub(crate) fn start_traffic_filter(&mut self){
+		if self.traffic.filter.is_none() {
+			self.traffic.filter = Some((String::new(), 0));
+		}
+	}
+
+	// returns false if there was no filter to clear
+	pub(crate) fn clear_traffic_filter(&mut self) -> bool{
+		self.traffic.filter.take().is_some()
+	}
+
+	pub(crate) fn is_filtering_traffic(&self) -> bool{
+		self.traffic.filter.is_some()
+	}
+
+	pub(crate) fn edit_traffic_filter(&mut self, op: EditOp) -> bool{
+		let changed = match &mut self.traffic.filter{
+			Some((filter, cursor)) => line_edit::apply(filter, cursor, op),
+			None => return false,
+		};
+		if changed {
+			self.traffic.selected = None;
+		}
+		changed
+	}
+
+	// write the messages that match the filter to a file
+	pub(crate) fn export_traffic(&mut self){
+		let path = self.config.traffic.export_path.clone();
+		let visible = self.traffic.visible();
+		let count = visible.len();
+		match write_traffic(&path, &visible){
+			Ok(()) => self.add_notice(format!("Exported {} messages to {}", count, path)),
+			Err(e) => self.add_notice(format!("Failed to export messages to {}: {}", path, e)),
+		}
+	}
+}
+
+// one json object per line
+fn write_traffic(path: &str, entries: &[&TrafficRecord]) -> io::Result<()>{
+	let mut file = BufWriter::new(File::create(path)?);
+	for entry in entries{
+		let line = json!({
+			"time": entry.time.to_rfc3339(),
+			"direction": entry.direction.to_string(),
+			"kind": entry.kind,
+			"message": entry.message,
+		});
+		writeln!(file, "{}", line)?;
+	}
+	file.flush()
+}
+
+// the names of the nested enum variants at the top of a serialized message
+fn message_kind(value: &Value) -> String{
+	let mut names = Vec::new();
+	let mut value = value;
+	while names.len() < 2 {
+		match value {
+			Value::Object(map) if map.len() == 1 => {
+				let (name, inner) = map.iter().next().expect("the map has one entry");
+				names.push(name.clone());
+				value = inner;
+			},
+			Value::String(name) if !names.is_empty() => {
+				names.push(name.clone()); // a variant without fields
+				break;
+			},
+			_ => break,
+		}
+	}
+	if names.is_empty() {
+		return String::from("?");
+	}
+	names.join("/")
+}
@@ -1,14 +1,38 @@
 
+use chrono::Local;
+use crossterm::event::KeyModifiers;
+use tracing::{debug, instrument, warn};
 use spider_client::message::{UiMessage, UiInput, Message, UiElementKind};
 
-use crate::{model::{update::ModelUpdate}, renderer::Renderer};
+use crate::{model::{update::{ModelUpdate, ConnectionState}}, renderer::{Renderer, HitTarget}, keymap::KeyResult};
 
-use super::{ModelProcessor, ModelView, page_state::SelectDirection};
+use super::{ModelProcessor, ModelView, line_edit::{self, EditOp}, status::StatusItem};
 
 
 
 impl<R: Renderer> ModelProcessor<R>{
+	// whether an update may change what is on screen, so that the screen is only drawn when it does.
+	// Datasets are compared with what was there when they arrive instead
+	fn changes_view(&self, update: &ModelUpdate) -> bool{
+		match update{
+			ModelUpdate::Event(crossterm::event::Event::Key(key)) => key.kind != crossterm::event::KeyEventKind::Release,
+			ModelUpdate::Event(crossterm::event::Event::Mouse(mouse)) => mouse.kind != crossterm::event::MouseEventKind::Moved,
+			ModelUpdate::Event(crossterm::event::Event::FocusGained) => false,
+			ModelUpdate::Event(crossterm::event::Event::FocusLost) => false,
+			ModelUpdate::Event(_) => true,
+			ModelUpdate::UpdateDataset(_, _) => false,
+			ModelUpdate::ConnectionState(state) => *state != self.connection,
+			ModelUpdate::Dataset(_) => matches!(self.view, ModelView::Datasets),
+			ModelUpdate::Traffic(_) => matches!(self.view, ModelView::Traffic),
+			_ => true,
+		}
+	}
+
+	#[instrument(level = "debug", skip_all)]
 	pub(crate) fn update(&mut self, update: ModelUpdate){
+		if self.changes_view(&update) {
+			self.dirty = true;
+		}
 		match update{
 			ModelUpdate::Event(event) => {
 				match event{
@@ -16,175 +40,87 @@ impl<R: Renderer> ModelProcessor<R>{
 						if let crossterm::event::KeyEventKind::Release = key.kind {
 							return; // Dont respond to key-up events
 						}
-						match key.code{
-							crossterm::event::KeyCode::Backspace => {
-								if let Some((mgr, state)) = self.get_current_mgr_state_mut(){
-									let input = state.get_selected_uncommited_input_mut();
-									match input{
-										Some(input) => {
-											input.pop();
-										},
-										None => {},
-									}
-								}
-							},
-							crossterm::event::KeyCode::Enter => {
-								match self.view{
-									ModelView::List => self.view = ModelView::Page,
-									ModelView::Page => {
-										// send input message!
-										match self.get_current_mgr_state_mut(){
-											Some((mgr, state)) => {
-												match state.get_selected_id() {
-													Some(id) => {
-														match mgr.get_by_id(id){
-															Some(elem) => {
-																let selected_datum = state.get_selected_datum();
-																let mut elem_kind = elem.kind().clone();
-																elem_kind = elem_kind.resolve(&selected_datum.as_ref());
-
-																match elem_kind{
-																	UiElementKind::TextEntry => {
-																		let page_id = mgr.get_page().id().clone();
-																		let id = id.clone();
-																		if let Some(text) = state.take_selected_uncommited_input_mut(){
-																			let dataset_ids = state.get_selected_datasets().clone();
-																			let msg = Message::Ui(UiMessage::InputFor(page_id, id, dataset_ids, UiInput::Text(text)));
-																			self.sender.blocking_send(msg);
-																		}
-																	},
-																	UiElementKind::Button => {
-																		let page_id = mgr.get_page().id().clone();
-																		let dataset_ids = state.get_selected_datasets().clone();
-																		let msg = Message::Ui(UiMessage::InputFor(page_id, id.to_string(), dataset_ids, UiInput::Click));
-																		self.sender.blocking_send(msg);
-																	},
-																	_ => {}
-																}
-															},
-															None => {}, // non-existant element cant update
-														}
-													},
-													None => {},
-												}
-											},
-											None => {},
+						let mode = self.key_mode();
+						match self.keymap.resolve(mode, key){
+							KeyResult::Action(action) => self.perform(action),
+							KeyResult::Pending => {}, // wait for the rest of the chord
+							KeyResult::Unbound => {
+								// type characters into the selected input
+								if let crossterm::event::KeyCode::Char(ch) = key.code {
+									// alt and a number switches to that tab
+									if key.modifiers == KeyModifiers::ALT {
+										if let Some(digit @ 1..=9) = ch.to_digit(10) {
+											self.select_tab(digit as usize - 1);
 										}
-									},
-								}
-							},
-							crossterm::event::KeyCode::Left => {
-								match self.view {
-									ModelView::List => {},
-									ModelView::Page => {
-										if let Some((mgr, state, data_map)) = self.get_context(){
-											state.select_next(mgr, data_map, SelectDirection::Left);
+									}
+									if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+										match self.view{
+											ModelView::List => self.type_in_page_list(ch),
+											_ => {self.edit_selected_input(EditOp::Insert(String::from(ch)));},
 										}
-									},
+									}
 								}
 							},
-							crossterm::event::KeyCode::Right => {
-								match self.view {
-									ModelView::List => {},
-									ModelView::Page => {
-										if let Some((mgr, state, data_map)) = self.get_context(){
-											state.select_next(mgr, data_map, SelectDirection::Right);
-										}
-									},
-								}
+						}
+					},
+					
+					crossterm::event::Event::Paste(str) => {
+						// insert paste into currently selected input
+						self.edit_selected_input(EditOp::Insert(str));
+					},
+					crossterm::event::Event::Mouse(mouse) => {
+						match mouse.kind{
+							crossterm::event::MouseEventKind::Down(crossterm::event::MouseButton::Left) => {
+								self.click(mouse.column, mouse.row);
 							},
-							crossterm::event::KeyCode::Up => {
+							crossterm::event::MouseEventKind::ScrollUp => {
 								match self.view {
-									ModelView::List => self.select_prev_page(),
+									ModelView::Menu => self.select_prev_menu_entry(),
+									ModelView::List => self.move_visible_selection(true),
+									ModelView::Datasets => self.select_prev_dataset_row(),
+									ModelView::Traffic => self.move_traffic_selection(-3),
 									ModelView::Page => {
-										if let Some((mgr, state, data_map)) = self.get_context(){
-											state.select_next(mgr, data_map, SelectDirection::Up);
+										self.focus_pane_at(mouse.column, mouse.row);
+										if let Some(state) = self.get_viewed_page_state(){
+											state.scroll_by(-3);
 										}
 									},
 								}
 							},
-							crossterm::event::KeyCode::Down => {
+							crossterm::event::MouseEventKind::ScrollDown => {
 								match self.view {
-									ModelView::List => self.select_next_page(),
+									ModelView::Menu => self.select_next_menu_entry(),
+									ModelView::List => self.move_visible_selection(false),
+									ModelView::Datasets => self.select_next_dataset_row(),
+									ModelView::Traffic => self.move_traffic_selection(3),
 									ModelView::Page => {
-										if let Some((mgr, state, data_map)) = self.get_context(){
-											state.select_next(mgr, data_map, SelectDirection::Down);
+										self.focus_pane_at(mouse.column, mouse.row);
+										if let Some(state) = self.get_viewed_page_state(){
+											state.scroll_by(3);
 										}
 									},
 								}
 							},
-							// crossterm::event::KeyCode::Home => todo!(),
-							// crossterm::event::KeyCode::End => todo!(),
-							// crossterm::event::KeyCode::PageUp => todo!(),
-							// crossterm::event::KeyCode::PageDown => todo!(),
-							// crossterm::event::KeyCode::Tab => todo!(),
-							// crossterm::event::KeyCode::BackTab => todo!(),
-							// crossterm::event::KeyCode::Delete => todo!(),
-							// crossterm::event::KeyCode::Insert => todo!(),
-							// crossterm::event::KeyCode::F(_) => todo!(),
-							crossterm::event::KeyCode::Char(ch) => {
-								if ch == 'q' {
-									match self.view{
-										ModelView::List => self.exit = true,
-										ModelView::Page => {},
-									}
-								}
-								
-								// append character to currently selected input
-								if let ModelView::Page = self.view {
-									if let Some((mgr, state)) = self.get_current_mgr_state_mut(){
-										let input = state.get_selected_uncommited_input_mut();
-										match input{
-											Some(input) => {
-												input.push(ch);
-											},
-											None => {
-												state.set_selected_uncommited_input(String::from(ch));
-											},
-										}
-									}
-								}
-							},
-							// crossterm::event::KeyCode::Null => todo!(),
-							crossterm::event::KeyCode::Esc => {
-								match self.view{
-									ModelView::List => {},
-									ModelView::Page => self.view = ModelView::List,
-								}
-							},
 							_ => {},
 						}
 					},
-					
-					crossterm::event::Event::Paste(str) => {
-						// append paste to currently selected input
-						if let ModelView::Page = self.view {
-							if let Some((_, state)) = self.get_current_mgr_state_mut(){
-								let input = state.get_selected_uncommited_input_mut();
-								match input{
-									Some(input) => {
-										input.push_str(&str);
-									},
-									None => {
-										state.set_selected_uncommited_input(str);
-									},
-								}
-							}
-						}
-					},
-					crossterm::event::Event::Mouse(_) => {},
 					crossterm::event::Event::Resize(_, _) => {},
 					crossterm::event::Event::FocusGained => {},
 					crossterm::event::Event::FocusLost => {},
 				}
 			},
 			ModelUpdate::SetPages(pages) => {
+				self.last_update = Some(Local::now());
 				self.set_pages(pages);
+				self.sync_tabs();
 			},
 			ModelUpdate::SetPage(page) => {
+				self.last_update = Some(Local::now());
 				self.upsert_page(page);
+				self.sync_tabs();
 			},
 			ModelUpdate::UpdateElementsFor(id, elements) => {
+				self.last_update = Some(Local::now());
 				match self.page_set.get_page_mut(&id){
 					Some(page) => {
 						page.apply_changes(elements);
@@ -193,9 +129,157 @@ impl<R: Renderer> ModelProcessor<R>{
 				}
 			},
     		ModelUpdate::UpdateDataset(path, dataset) => {
+				let now = Local::now();
+				// the time is shown to the second, if at all, so an unchanged dataset is drawn at most once a second
+				let second_changed = self.shows_status_item(StatusItem::LastUpdate)
+					&& self.last_update.map(|time| time.timestamp()) != Some(now.timestamp());
+				self.last_update = Some(now);
+				if self.record_dataset_update(&path, &dataset) || second_changed {
+					self.dirty = true;
+				}
 				self.datasets.insert(path, dataset);
 			},
+			ModelUpdate::ConnectionState(state) => {
+				let reconnected = state == ConnectionState::Connected && self.connection != ConnectionState::Connected;
+				self.connection = state;
+				if reconnected {
+					self.announce();
+				}
+			},
+			ModelUpdate::ClearPage => {
+				// the base clears the page this client serves
+				let id = self.own_id.clone();
+				self.remove_page(&id);
+				self.sync_tabs();
+			},
+			ModelUpdate::UpdateElements(elements) => {
+				self.last_update = Some(Local::now());
+				if let Some(page) = self.page_set.get_page_mut(&self.own_id) {
+					page.apply_changes(elements);
+				}
+			},
+			ModelUpdate::Router(msg) => {
+				self.add_notice(format!("Router: {:?}", msg));
+			},
+			ModelUpdate::Dataset(msg) => {
+				debug!(?msg, "Dataset message from base");
+				self.record_dataset_message(&msg);
+			},
+			ModelUpdate::Traffic(entry) => {
+				self.record_traffic(entry);
+			},
+			ModelUpdate::Notice(text) => {
+				self.add_notice(text);
+			},
+		}
+	}
+
+	// respond to a click on whatever was drawn at that position
+	pub(crate) fn click(&mut self, x: u16, y: u16){
+		self.focus_pane_at(x, y);
+		let target = match self.hit_map.hit(x, y){
+			Some(target) => target.clone(),
+			None => return,
+		};
+		match target{
+			HitTarget::MenuItem(index) => {
+				self.menu_index = index;
+				self.activate_menu_entry();
+			},
+			HitTarget::PageListItem(index) => self.open_visible_page(index),
+			HitTarget::Tab(index) => self.select_tab(index),
+			HitTarget::Pane(_) => {}, // focused above
+			HitTarget::TrafficRow(index) => self.select_traffic_row(index),
+			HitTarget::DatasetRow(index) => {
+				self.select_dataset_row(index);
+				self.expand_dataset_row(None);
+			},
+			HitTarget::Element { id, dataset_indices, datum } => {
+				if let Some((_, state)) = self.get_current_mgr_state_mut(){
+					state.select(id, dataset_indices, datum);
+				}
+				if let Some(UiElementKind::Button) = self.get_selected_kind(){
+					self.submit_selected();
+				}
+			},
+		}
+	}
+
+	// send the input for the selected element
+	pub(crate) fn submit_selected(&mut self){
+		// send input message!
+		match self.get_current_mgr_state_mut(){
+			Some((mgr, state)) => {
+				match state.get_selected_id() {
+					Some(id) => {
+						match mgr.get_by_id(id){
+							Some(elem) => {
+								let selected_datum = state.get_selected_datum();
+								let mut elem_kind = elem.kind().clone();
+								elem_kind = elem_kind.resolve(&selected_datum.as_ref());
+
+								match elem_kind{
+									UiElementKind::TextEntry => {
+										let page_id = mgr.get_page().id().clone();
+										let id = id.clone();
+										if let Some(text) = state.take_selected_uncommited_input_mut(){
+											let dataset_ids = state.get_selected_datasets().clone();
+											let msg = Message::Ui(UiMessage::InputFor(page_id, id, dataset_ids, UiInput::Text(text)));
+											if self.sender.blocking_send(msg).is_err() {
+												warn!("Failed to send input to the base, the connection has closed");
+											}
+										}
+									},
+									UiElementKind::Button => {
+										let page_id = mgr.get_page().id().clone();
+										let dataset_ids = state.get_selected_datasets().clone();
+										let msg = Message::Ui(UiMessage::InputFor(page_id, id.to_string(), dataset_ids, UiInput::Click));
+										if self.sender.blocking_send(msg).is_err() {
+											warn!("Failed to send a click to the base, the connection has closed");
+										}
+									},
+									_ => {}
+								}
+							},
+							None => {}, // non-existant element cant update
+						}
+					},
+					None => {},
+				}
+			},
+			None => {},
+		}
+	}
+
+	// whether the selected element on the current page is a text entry
+	pub(crate) fn selected_is_text_entry(&mut self) -> bool{
+		if !matches!(self.view, ModelView::Page) {
+			return false;
+		}
+		match self.get_selected_kind(){
+			Some(UiElementKind::TextEntry) => true,
+			_ => false,
+		}
+	}
+
+	// apply an edit to the selected input, if it is a text entry on the current page
+	pub(crate) fn edit_selected_input(&mut self, op: EditOp) -> bool{
+		if let Some((name, cursor)) = &mut self.renaming {
+			return line_edit::apply(name, cursor, op);
+		}
+		if let ModelView::List = self.view {
+			return self.edit_page_filter(op);
+		}
+		if let ModelView::Traffic = self.view {
+			return self.edit_traffic_filter(op);
+		}
+		if !self.selected_is_text_entry() {
+			return false;
+		}
+		match self.get_current_mgr_state_mut(){
+			Some((_, state)) => state.edit_selected_input(op),
+			None => false,
 		}
 	}
 
-}
\ No newline at end of file
+}
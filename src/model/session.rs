@@ -0,0 +1,160 @@
+// Recording of every update fed to the model, so that a session can be replayed exactly.
+// A session file is json lines: a header saying how the model started, then one line per update.
+// Each model start is its own session, so after a profile switch the next goes to a numbered file.
+
+use std::{fs::File, io::{self, BufRead, BufReader, BufWriter, Write}, path::{Path, PathBuf}, time::Instant};
+
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+
+use crate::{config::SpiderTuiConfig, error::StartupError};
+
+use super::update::ModelUpdate;
+
+// What the updates alone do not say about the start of a session
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct SessionHeader {
+    pub profile: String,
+    #[serde(default)]
+    pub config: Option<SpiderTuiConfig>, // as it was, with the command line applied
+    #[serde(default)]
+    pub tabs: Vec<String>, // restored from the session before
+    #[serde(default)]
+    pub active_tab: usize,
+}
+
+#[derive(Deserialize)]
+pub struct RecordedUpdate {
+    pub millis: u64, // since the session started
+    pub update: ModelUpdate,
+}
+
+#[derive(Serialize)]
+struct RecordedUpdateRef<'a> {
+    millis: u64,
+    update: &'a ModelUpdate,
+}
+
+pub struct SessionRecorder {
+    file: BufWriter<File>,
+    start: Instant,
+}
+
+impl SessionRecorder {
+    pub fn create(path: &Path, header: &SessionHeader) -> io::Result<Self> {
+        let mut file = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut file, header)?;
+        writeln!(file)?;
+        file.flush()?;
+        Ok(Self {
+            file,
+            start: Instant::now(),
+        })
+    }
+
+    pub fn record(&mut self, update: &ModelUpdate) {
+        // the traffic monitor's entries are copies of the other updates
+        if let ModelUpdate::Traffic(_) = update {
+            return;
+        }
+        let line = RecordedUpdateRef {
+            millis: self.start.elapsed().as_millis() as u64,
+            update,
+        };
+        // flushed every line, so that the session survives a crash
+        let result = serde_json::to_writer(&mut self.file, &line)
+            .map_err(io::Error::from)
+            .and_then(|_| writeln!(self.file))
+            .and_then(|_| self.file.flush());
+        if let Err(e) = result {
+            warn!("Failed to record an update: {}", e);
+        }
+    }
+}
+
+// the configured path for the first session of a run, then name.2.jsonl, name.3.jsonl and so on
+#[derive(Default)]
+pub struct SessionNamer {
+    sessions: usize, // started so far in this run
+}
+
+impl SessionNamer {
+    pub fn next(&mut self, path: &Path) -> PathBuf {
+        self.sessions += 1;
+        if self.sessions == 1 {
+            return path.to_path_buf();
+        }
+        let stem = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+        let name = match path.extension() {
+            Some(ext) => format!("{}.{}.{}", stem, self.sessions, ext.to_string_lossy()),
+            None => format!("{}.{}", stem, self.sessions),
+        };
+        path.with_file_name(name)
+    }
+}
+
+pub fn load_session(path: &Path) -> Result<(SessionHeader, Vec<RecordedUpdate>), StartupError> {
+    let file = File::open(path).map_err(|e| StartupError::SessionRead(path.to_path_buf(), e))?;
+    let mut lines = BufReader::new(file).lines();
+
+    let header = lines.next().unwrap_or_else(|| Ok(String::new()));
+    let header = header.map_err(|e| StartupError::SessionRead(path.to_path_buf(), e))?;
+    let header = serde_json::from_str(&header).map_err(|e| StartupError::SessionParse(path.to_path_buf(), 1, e))?;
+
+    let mut updates = Vec::new();
+    for (i, line) in lines.enumerate() {
+        let line = line.map_err(|e| StartupError::SessionRead(path.to_path_buf(), e))?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let update = serde_json::from_str(&line).map_err(|e| StartupError::SessionParse(path.to_path_buf(), i + 2, e))?;
+        updates.push(update);
+    }
+    Ok((header, updates))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::env;
+
+    use crossterm::event::{Event, KeyCode, KeyEvent, KeyModifiers};
+
+    use crate::model::update::ConnectionState;
+
+    use super::*;
+
+    #[test]
+    fn recorded_session_loads() {
+        let path = env::temp_dir().join(format!("spider_tui_session_{}.jsonl", std::process::id()));
+        let mut config: SpiderTuiConfig = serde_json::from_str("{}").unwrap();
+        config.frame_rate = 12;
+        let header = SessionHeader {
+            profile: String::from("home"),
+            config: Some(config),
+            tabs: vec![String::from("owner/Alpha")],
+            active_tab: 0,
+        };
+        let mut recorder = SessionRecorder::create(&path, &header).unwrap();
+        recorder.record(&ModelUpdate::ConnectionState(ConnectionState::Reconnecting(5)));
+        recorder.record(&ModelUpdate::Event(Event::Key(KeyEvent::new(KeyCode::Char('q'), KeyModifiers::NONE))));
+        drop(recorder);
+
+        let (loaded, updates) = load_session(&path).unwrap();
+        let _ = std::fs::remove_file(&path);
+        assert_eq!(loaded.profile, header.profile);
+        assert_eq!(loaded.tabs, header.tabs);
+        assert_eq!(loaded.config.map(|config| config.frame_rate), Some(12));
+        assert_eq!(updates.len(), 2);
+        assert!(matches!(updates[0].update, ModelUpdate::ConnectionState(ConnectionState::Reconnecting(5))));
+        assert!(matches!(updates[1].update, ModelUpdate::Event(Event::Key(KeyEvent { code: KeyCode::Char('q'), .. }))));
+        assert!(updates[0].millis <= updates[1].millis);
+    }
+
+    #[test]
+    fn later_sessions_are_numbered() {
+        let mut namer = SessionNamer::default();
+        assert_eq!(namer.next(Path::new("logs/session.jsonl")), Path::new("logs/session.jsonl"));
+        assert_eq!(namer.next(Path::new("logs/session.jsonl")), Path::new("logs/session.2.jsonl"));
+        assert_eq!(namer.next(Path::new("logs/session")), Path::new("logs/session.3"));
+    }
+}
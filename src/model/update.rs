@@ -1,13 +1,111 @@
+use std::fmt;
+
+use chrono::{DateTime, Duration, Local};
 use crossterm::event::Event;
+use serde::{Serialize, Deserialize};
 use spider_client::{
-    message::{AbsoluteDatasetPath, DatasetData, UiElementUpdate, UiPage},
+    message::{AbsoluteDatasetPath, DatasetData, DatasetMessage, Message, RouterMessage, UiElementUpdate, UiPage},
     SpiderId2048,
 };
 
+#[derive(Serialize, Deserialize)]
 pub enum ModelUpdate {
     Event(Event),
     SetPages(Vec<UiPage>),
     SetPage(UiPage),
     UpdateElementsFor(SpiderId2048, Vec<UiElementUpdate>),
     UpdateDataset(AbsoluteDatasetPath, Vec<DatasetData>),
+    ConnectionState(ConnectionState),
+    ClearPage, // of the page this client serves
+    UpdateElements(Vec<UiElementUpdate>), // to the page this client serves
+    Router(RouterMessage),
+    Dataset(DatasetMessage),
+    Notice(String), // something to show the user from outside the model
+    #[serde(skip)]
+    Traffic(TrafficEntry), // a message passed between the base and the model, not recorded
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ConnectionState {
+    #[default]
+    Connected,
+    Connecting,
+    Reconnecting(u64), // seconds until the next attempt
+}
+
+impl fmt::Display for ConnectionState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConnectionState::Connected => write!(f, "Connected"),
+            ConnectionState::Connecting => write!(f, "Connecting"),
+            ConnectionState::Reconnecting(secs) => write!(f, "Reconnecting in {}s", secs),
+        }
+    }
+}
+
+// how long a notice stays on screen
+const NOTICE_SECONDS: i64 = 30;
+
+// Something to tell the user that does not belong to a page
+#[derive(Debug, Clone)]
+pub struct Notice {
+    pub time: DateTime<Local>,
+    pub text: String,
+}
+
+impl Notice {
+    pub fn new(text: String) -> Self {
+        Self {
+            time: Local::now(),
+            text,
+        }
+    }
+
+    // when it is no longer shown
+    pub fn expires(&self) -> DateTime<Local> {
+        self.time + Duration::seconds(NOTICE_SECONDS)
+    }
+}
+
+// What the status bar shows, prepared by the model
+#[derive(Debug, Clone, Default)]
+pub struct Status {
+    pub connection: ConnectionState,
+    pub segments: Vec<String>,
+    pub hints: Vec<(String, String)>, // key and what it does
+    pub debug: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TrafficDirection {
+    Inbound,  // from the base
+    Outbound, // to the base
+}
+
+impl fmt::Display for TrafficDirection {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TrafficDirection::Inbound => write!(f, "in"),
+            TrafficDirection::Outbound => write!(f, "out"),
+        }
+    }
+}
+
+// A message passed between the base and the model, for the traffic monitor.
+// It is only serialized once it reaches the monitor, to keep the connection quick.
+#[derive(Debug, Clone)]
+pub struct TrafficEntry {
+    pub time: DateTime<Local>,
+    pub direction: TrafficDirection,
+    pub message: Message,
+}
+
+impl TrafficEntry {
+    pub fn new(direction: TrafficDirection, message: &Message) -> Self {
+        Self {
+            time: Local::now(),
+            direction,
+            message: message.clone(),
+        }
+    }
 }
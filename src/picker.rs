@@ -0,0 +1,55 @@
+// Choosing which base to connect to, at startup and when switching from the page list.
+
+use crossterm::event::{Event, KeyEventKind, MouseButton, MouseEventKind};
+use tokio::sync::mpsc::Receiver;
+
+use crate::{
+    config::{SpiderTuiConfig, Profile},
+    keymap::{Action, Keymap, KeyMode, KeyResult},
+    renderer::{Renderer, HitTarget, tui::TUI},
+};
+
+
+
+// Returns the chosen profile, or None if the user backed out
+pub async fn run(renderer: &mut TUI, profiles: &[Profile], current: Option<&Profile>, config: &SpiderTuiConfig, events: &mut Receiver<Event>) -> Option<Profile>{
+    if profiles.is_empty() {
+        return None;
+    }
+    let mut keymap = Keymap::from_config(&config.keybindings);
+    let mut selected = current
+        .and_then(|current| profiles.iter().position(|profile| profile.name == current.name))
+        .unwrap_or(0);
+    renderer.startup();
+
+    loop{
+        let items = profiles.iter().map(|profile| {
+            match current{
+                Some(current) if current.name == profile.name => format!("{} (connected)", profile.name),
+                _ => profile.name.clone(),
+            }
+        }).collect();
+        let hits = renderer.render_choice_list("Select Profile (q=Back)", items, selected);
+
+        let event = events.recv().await?;
+        match event{
+            Event::Key(key) if key.kind != KeyEventKind::Release => {
+                match keymap.resolve(KeyMode::List, key){
+                    KeyResult::Action(Action::NavigateUp) => selected = selected.saturating_sub(1),
+                    KeyResult::Action(Action::NavigateDown) => selected = (selected + 1).min(profiles.len() - 1),
+                    KeyResult::Action(Action::Submit) => return Some(profiles[selected].clone()),
+                    KeyResult::Action(Action::Quit) | KeyResult::Action(Action::OpenMenu) => return None,
+                    _ => {},
+                }
+            },
+            Event::Mouse(mouse) if mouse.kind == MouseEventKind::Down(MouseButton::Left) => {
+                if let Some(HitTarget::PageListItem(index)) = hits.hit(mouse.column, mouse.row) {
+                    if let Some(profile) = profiles.get(*index) {
+                        return Some(profile.clone());
+                    }
+                }
+            },
+            _ => {},
+        }
+    }
+}
@@ -0,0 +1,190 @@
+// A viewport onto a page that may be taller than the screen.
+// Elements are laid out in page coordinates, where the top of the page is row 0,
+// and are translated onto the screen. Anything outside the viewport is clipped.
+
+use std::collections::HashMap;
+
+use spider_client::message::{DatasetData, UiElement};
+use tui::{buffer::Buffer, layout::Rect, style::Style, widgets::Widget};
+
+use super::{HitMap, HitTarget, Region, theme::Theme};
+
+
+
+pub struct Canvas{
+	buffer: Buffer, // area is the viewport on screen
+	theme: Theme,
+	background: Style,
+	scroll: u16, // page row shown at the top of the viewport
+	cursor: Option<(u16, u16)>, // in page coordinates
+	selected: Option<Rect>, // in page coordinates
+	hits: HitMap, // in screen coordinates
+	layout: LayoutCache,
+}
+
+// Sizes of the elements measured so far in this frame. Containers measure their children, which
+// measure theirs, so without this the same elements are measured again at every level.
+// Elements and data are told apart by address, so this is only valid while they are unchanged.
+#[derive(Default)]
+pub struct LayoutCache{
+	heights: HashMap<(usize, usize), u16>,
+	widths: HashMap<(usize, usize), u16>,
+}
+
+impl LayoutCache{
+	fn key(elem: &UiElement, data: &Option<&DatasetData>) -> (usize, usize){
+		let datum = data.map_or(0, |datum| datum as *const DatasetData as usize);
+		(elem as *const UiElement as usize, datum)
+	}
+
+	pub fn height(&self, elem: &UiElement, data: &Option<&DatasetData>) -> Option<u16>{
+		self.heights.get(&Self::key(elem, data)).copied()
+	}
+
+	pub fn set_height(&mut self, elem: &UiElement, data: &Option<&DatasetData>, height: u16){
+		self.heights.insert(Self::key(elem, data), height);
+	}
+
+	pub fn width(&self, elem: &UiElement, data: &Option<&DatasetData>) -> Option<u16>{
+		self.widths.get(&Self::key(elem, data)).copied()
+	}
+
+	pub fn set_width(&mut self, elem: &UiElement, data: &Option<&DatasetData>, width: u16){
+		self.widths.insert(Self::key(elem, data), width);
+	}
+}
+
+impl Canvas{
+	pub fn new(viewport: Rect, scroll: u16, theme: &Theme) -> Self{
+		let background = theme.background.style();
+		let mut buffer = Buffer::empty(viewport);
+		buffer.set_style(viewport, background);
+		Self{
+			buffer,
+			theme: theme.clone(),
+			background,
+			scroll,
+			cursor: None,
+			selected: None,
+			hits: HitMap::new(),
+			layout: LayoutCache::default(),
+		}
+	}
+
+	// carry on with the sizes measured before this canvas
+	pub fn with_layout(mut self, layout: LayoutCache) -> Self{
+		self.layout = layout;
+		self
+	}
+
+	pub fn layout(&mut self) -> &mut LayoutCache{
+		&mut self.layout
+	}
+
+	pub fn take_layout(&mut self) -> LayoutCache{
+		std::mem::take(&mut self.layout)
+	}
+
+	pub fn theme(&self) -> &Theme{
+		&self.theme
+	}
+
+	// the area of the page that the canvas will draw elements into
+	pub fn page_area(&self, page_height: u16) -> Rect{
+		let viewport = self.buffer.area;
+		Rect{
+			x: viewport.x,
+			y: 0,
+			width: viewport.width,
+			height: page_height.max(viewport.height),
+		}
+	}
+
+	pub fn render_widget<W: Widget>(&mut self, widget: W, rect: Rect){
+		let viewport = self.buffer.area;
+		let top = self.scroll;
+		let bottom = self.scroll.saturating_add(viewport.height);
+		let left = rect.left().max(viewport.left());
+		let right = rect.right().min(viewport.right());
+		if rect.width == 0 || rect.height == 0 || left >= right {
+			return;
+		}
+		if rect.top() >= bottom || rect.bottom() <= top {
+			return; // not visible
+		}
+
+		if rect.top() >= top && rect.bottom() <= bottom && left == rect.left() && right == rect.right() {
+			// entirely visible, render straight into the viewport
+			let screen_rect = Rect{
+				x: rect.x,
+				y: viewport.y + rect.y - top,
+				width: rect.width,
+				height: rect.height,
+			};
+			widget.render(screen_rect, &mut self.buffer);
+			return;
+		}
+
+		// partially visible, render on its own and copy the visible part
+		let mut scratch = Buffer::empty(Rect{ x: rect.x, y: 0, width: rect.width, height: rect.height });
+		scratch.set_style(scratch.area, self.background);
+		widget.render(scratch.area, &mut scratch);
+		for y in rect.top().max(top)..rect.bottom().min(bottom){
+			for x in left..right{
+				*self.buffer.get_mut(x, viewport.y + y - top) = scratch.get(x, y - rect.y).clone();
+			}
+		}
+	}
+
+	pub fn set_cursor(&mut self, x: u16, y: u16){
+		self.cursor = Some((x, y));
+	}
+
+	// the cursor position on screen, if it is within the viewport
+	pub fn screen_cursor(&self) -> Option<(u16, u16)>{
+		let viewport = self.buffer.area;
+		match self.cursor{
+			Some((x, y)) if y >= self.scroll && y < self.scroll.saturating_add(viewport.height) => {
+				Some((x, viewport.y + y - self.scroll))
+			},
+			_ => None,
+		}
+	}
+
+	pub fn set_selected(&mut self, rect: Rect){
+		self.selected = Some(rect);
+	}
+
+	pub fn get_selected(&self) -> Option<Rect>{
+		self.selected
+	}
+
+	// record the visible part of an element so that it can be clicked
+	pub fn add_hit(&mut self, rect: Rect, target: HitTarget){
+		let viewport = self.buffer.area;
+		let top = rect.top().max(self.scroll);
+		let bottom = rect.bottom().min(self.scroll.saturating_add(viewport.height));
+		let left = rect.left().max(viewport.left());
+		let right = rect.right().min(viewport.right());
+		if top >= bottom || left >= right {
+			return;
+		}
+		let region = Region{
+			x: left,
+			y: viewport.y + top - self.scroll,
+			width: right - left,
+			height: bottom - top,
+		};
+		self.hits.insert(region, target);
+	}
+
+	pub fn take_hits(&mut self) -> HitMap{
+		std::mem::take(&mut self.hits)
+	}
+}
+
+impl Widget for Canvas{
+	fn render(self, _area: Rect, buf: &mut Buffer) {
+		buf.merge(&self.buffer);
+	}
+}
@@ -2,15 +2,87 @@ use std::collections::HashMap;
 
 use spider_client::message::{UiPage, DatasetData, AbsoluteDatasetPath};
 
-use crate::model::processor::PageState;
+use crate::model::{processor::{PageState, page_list::PageListView, split::SplitLayout, datasets::InspectorRow, traffic::TrafficView}, update::{Notice, Status}};
 
 
 pub trait Renderer: Sync + Send + 'static{
     fn startup(&mut self);
-    fn render_menu(&mut self);
-    fn render_page(&mut self, page: &UiPage, state: &PageState, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>);
-    fn render_page_list(&mut self, list: &Vec<&UiPage>, highlight_index: usize);
+    fn set_status(&mut self, status: &Status);
+    fn set_notice(&mut self, notice: Option<&Notice>);
+    fn set_tabs(&mut self, tabs: &[String], active: usize);
+    fn render_menu(&mut self, entries: &[&str], highlight_index: usize, details: &[String]) -> HitMap;
+    fn render_page(&mut self, page: &UiPage, state: &mut PageState, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) -> HitMap;
+    // two pages at once, the first is the one selected in the page list
+    fn render_split(&mut self, panes: [(&UiPage, &mut PageState); 2], layout: &SplitLayout, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) -> HitMap;
+    fn render_page_list(&mut self, view: &PageListView) -> HitMap;
+    fn render_datasets(&mut self, rows: &[InspectorRow], highlight_index: usize) -> HitMap;
+    fn render_traffic(&mut self, view: &TrafficView) -> HitMap;
     fn shutdown(self);
 }
 
+
+// What was drawn where, so that the model can respond to clicks
+#[derive(Debug, Clone)]
+pub enum HitTarget{
+    Element{
+        id: String,
+        dataset_indices: Vec<usize>,
+        datum: Option<DatasetData>,
+    },
+    PageListItem(usize),
+    MenuItem(usize),
+    Tab(usize),
+    Pane(usize), // the whole of a split pane, under its elements
+    DatasetRow(usize),
+    TrafficRow(usize),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Region{
+    pub x: u16,
+    pub y: u16,
+    pub width: u16,
+    pub height: u16,
+}
+
+impl Region{
+    pub fn contains(&self, x: u16, y: u16) -> bool{
+        x >= self.x && x < self.x.saturating_add(self.width) && y >= self.y && y < self.y.saturating_add(self.height)
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct HitMap{
+    regions: Vec<(Region, HitTarget)>,
+}
+
+impl HitMap{
+    pub fn new() -> Self{
+        Self::default()
+    }
+
+    pub fn insert(&mut self, region: Region, target: HitTarget){
+        self.regions.push((region, target));
+    }
+
+    // add the regions of another map, on top of these
+    pub fn extend(&mut self, other: HitMap){
+        self.regions.extend(other.regions);
+    }
+
+    // the target at a screen position. Later regions are drawn over earlier ones, so they take precedence.
+    pub fn hit(&self, x: u16, y: u16) -> Option<&HitTarget>{
+        self.hits(x, y).next()
+    }
+
+    // every target at a screen position, the topmost first
+    pub fn hits(&self, x: u16, y: u16) -> impl Iterator<Item = &HitTarget>{
+        self.regions.iter().rev().filter(move |(region, _)| region.contains(x, y)).map(|(_, target)| target)
+    }
+}
+
 pub mod tui;
+pub mod theme;
+mod canvas;
+#[cfg(test)]
+mod tests;
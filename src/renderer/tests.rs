@@ -0,0 +1,264 @@
+// Snapshot tests for the TUI renderer.
+// Each test renders a page with the headless renderer and compares the screen against a stored
+// text snapshot in src/renderer/snapshots. Running with UPDATE_SNAPSHOTS=1 records new snapshots
+// and rewrites existing ones after an intended layout change.
+
+use std::{collections::HashMap, env, fs, path::PathBuf};
+
+use spider_client::message::{AbsoluteDatasetPath, DatasetData, UiElementKind};
+
+use crate::{
+    model::{processor::{PageState, SelectDirection, page_list::{PageListEntry, PageListView, PageSort}, split::{SplitDirection, SplitLayout}, datasets::InspectorRow, traffic::TrafficView}, update::Status},
+    test_util::{button, container, page, text, text_entry},
+};
+
+use super::{theme::Theme, tui::TUI, HitTarget, Renderer};
+
+fn assert_snapshot(name: &str, actual: &str) {
+    let path = PathBuf::from(env!("CARGO_MANIFEST_DIR"))
+        .join("src/renderer/snapshots")
+        .join(format!("{}.txt", name));
+    if env::var_os("UPDATE_SNAPSHOTS").is_some() {
+        fs::write(&path, actual).expect("able to write snapshot");
+        return;
+    }
+    let expected = match fs::read_to_string(&path) {
+        Ok(expected) => expected,
+        Err(e) => panic!(
+            "snapshot {} could not be read ({}), rerun with UPDATE_SNAPSHOTS=1 to record it. The screen was:\n{}",
+            name, e, actual
+        ),
+    };
+    assert_eq!(
+        expected, actual,
+        "snapshot {} does not match, rerun with UPDATE_SNAPSHOTS=1 if the change is intended",
+        name
+    );
+}
+
+fn headless(width: u16, height: u16) -> TUI<tui::backend::TestBackend> {
+    TUI::headless(width, height, Theme::monochrome())
+}
+
+#[test]
+fn rows_page() {
+    let mgr = page(
+        "Test Page",
+        UiElementKind::Rows,
+        vec![text("Hello"), button("ok", "Ok")],
+    );
+    let mut state = PageState::default();
+    let mut renderer = headless(30, 8);
+    renderer.render_page(mgr.get_page(), &mut state, &HashMap::new());
+    assert_snapshot("rows_page", &renderer.screen_text());
+}
+
+#[test]
+fn page_list() {
+    let alpha = page("Alpha", UiElementKind::Rows, vec![]);
+    let beta = page("Beta", UiElementKind::Rows, vec![]);
+    let mut renderer = headless(30, 5);
+    let view = PageListView {
+        entries: vec![
+            PageListEntry { page: alpha.get_page(), matches: vec![] },
+            PageListEntry { page: beta.get_page(), matches: vec![] },
+        ],
+        highlight_index: 0,
+        filter: None,
+        sort: PageSort::Base,
+    };
+    let hits = renderer.render_page_list(&view);
+    assert_snapshot("page_list", &renderer.screen_text());
+    assert!(hits.hit(3, 2).is_some(), "list items should be clickable");
+}
+
+#[test]
+fn page_list_filtered() {
+    let alpha = page("Alpha", UiElementKind::Rows, vec![]);
+    let mut renderer = headless(30, 6);
+    let view = PageListView {
+        entries: vec![PageListEntry { page: alpha.get_page(), matches: vec![0, 3] }],
+        highlight_index: 0,
+        filter: Some("ah"),
+        sort: PageSort::Name,
+    };
+    renderer.render_page_list(&view);
+    assert_snapshot("page_list_filtered", &renderer.screen_text());
+}
+
+#[test]
+fn grid_page() {
+    let mgr = page(
+        "Test Page",
+        UiElementKind::Grid(2, 2),
+        vec![
+            button("a", "A"),
+            button("b", "B"),
+            button("c", "C"),
+            button("d", "D"),
+        ],
+    );
+    let data_map = HashMap::new();
+    let mut state = PageState::default();
+    state.select_next(&mgr, &data_map, SelectDirection::Down);
+    let mut renderer = headless(30, 9);
+    renderer.render_page(mgr.get_page(), &mut state, &data_map);
+    assert_snapshot("grid_page", &renderer.screen_text());
+}
+
+#[test]
+fn text_entry_with_input() {
+    let mgr = page(
+        "Test Page",
+        UiElementKind::Rows,
+        vec![text_entry("name", "Name")],
+    );
+    let data_map = HashMap::new();
+    let mut state = PageState::default();
+    state.select_next(&mgr, &data_map, SelectDirection::Down);
+    state.set_uncommited_input(String::from("name"), &vec![], String::from("abc"));
+    let mut renderer = headless(30, 8);
+    renderer.render_page(mgr.get_page(), &mut state, &data_map);
+    assert_snapshot("text_entry_with_input", &renderer.screen_text());
+}
+
+#[test]
+fn dataset_rows() {
+    let path = AbsoluteDatasetPath::new_public(vec![String::from("items")]);
+    let mut list = container(UiElementKind::Rows, vec![text("Item")]);
+    list.set_dataset(Some(path.clone()));
+    let mgr = page("Test Page", UiElementKind::Rows, vec![list]);
+    let mut data_map = HashMap::new();
+    data_map.insert(
+        path,
+        vec![
+            DatasetData::String(String::from("one")),
+            DatasetData::String(String::from("two")),
+            DatasetData::String(String::from("three")),
+        ],
+    );
+    let mut state = PageState::default();
+    let mut renderer = headless(30, 8);
+    renderer.render_page(mgr.get_page(), &mut state, &data_map);
+    assert_snapshot("dataset_rows", &renderer.screen_text());
+}
+
+#[test]
+fn scrolls_to_selection() {
+    let buttons = (0..10)
+        .map(|i| button(&format!("b{}", i), &format!("Button {}", i)))
+        .collect();
+    let mgr = page("Test Page", UiElementKind::Rows, buttons);
+    let data_map = HashMap::new();
+    let mut state = PageState::default();
+    let mut renderer = headless(30, 8);
+    renderer.render_page(mgr.get_page(), &mut state, &data_map);
+    assert!(!renderer.screen_text().contains("Button 9"));
+
+    for _ in 0..10 {
+        state.select_next(&mgr, &data_map, SelectDirection::Down);
+        renderer.render_page(mgr.get_page(), &mut state, &data_map);
+    }
+    assert_eq!(state.get_selected_id(), Some(&String::from("b9")));
+    assert!(state.get_scroll() > 0);
+    assert!(renderer.screen_text().contains("Button 9"));
+}
+
+#[test]
+fn status_bar() {
+    let mgr = page("Test Page", UiElementKind::Rows, vec![button("ok", "Ok")]);
+    let mut state = PageState::default();
+    let mut renderer = headless(40, 8);
+    renderer.set_status(&Status {
+        segments: vec![String::from("Connected"), String::from("Test Page")],
+        hints: vec![(String::from("enter"), String::from("press"))],
+        debug: Some(String::from("ok | []")),
+        ..Default::default()
+    });
+    renderer.render_page(mgr.get_page(), &mut state, &HashMap::new());
+    let screen = renderer.screen_text();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[6], "ok | []");
+    assert_eq!(lines[7], "Connected | Test Page        enter press");
+}
+
+#[test]
+fn tab_bar() {
+    let mgr = page("Beta", UiElementKind::Rows, vec![text("Hello")]);
+    let mut state = PageState::default();
+    let mut renderer = headless(30, 8);
+    renderer.set_tabs(&[String::from("Alpha"), String::from("Beta")], 1);
+    let hits = renderer.render_page(mgr.get_page(), &mut state, &HashMap::new());
+    let screen = renderer.screen_text();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[0], " 1 Alpha  2 Beta");
+    assert!(lines[1].starts_with("Beta═"));
+    assert!(matches!(hits.hit(3, 0), Some(HitTarget::Tab(0))));
+    assert!(matches!(hits.hit(12, 0), Some(HitTarget::Tab(1))));
+}
+
+#[test]
+fn split_side_by_side() {
+    let alpha = page("Alpha", UiElementKind::Rows, vec![text("Hello")]);
+    let beta = page("Beta", UiElementKind::Rows, vec![text("World")]);
+    let mut alpha_state = PageState::default();
+    let mut beta_state = PageState::default();
+    let mut renderer = headless(40, 8);
+    let layout = SplitLayout { direction: SplitDirection::SideBySide, ratio: 50, focus: 1 };
+    let panes = [(alpha.get_page(), &mut alpha_state), (beta.get_page(), &mut beta_state)];
+    let hits = renderer.render_split(panes, &layout, &HashMap::new());
+    let screen = renderer.screen_text();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[0], "Alpha═══════════════╔Beta═══════════════");
+    assert_eq!(lines[1], "Hello               ║World");
+    assert!(matches!(hits.hit(5, 3), Some(HitTarget::Pane(0))));
+    assert!(matches!(hits.hit(25, 3), Some(HitTarget::Pane(1))));
+}
+
+#[test]
+fn dataset_browser() {
+    let row = |depth, text: &str, expandable, expanded| InspectorRow {
+        depth,
+        text: String::from(text),
+        expandable,
+        expanded,
+        changed: false,
+    };
+    let rows = vec![
+        row(0, "sensors  1 entries", true, true),
+        row(1, "[0] {1}", true, true),
+        row(2, "temp: 21", false, false),
+    ];
+    let mut renderer = headless(30, 5);
+    let hits = renderer.render_datasets(&rows, 2);
+    let screen = renderer.screen_text();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[0], "╭Datasets────────────────────╮");
+    assert_eq!(lines[1], "│▾ sensors  1 entries        │");
+    assert_eq!(lines[2], "│  ▾ [0] {1}                 │");
+    assert_eq!(lines[3], "│      temp: 21              │");
+    assert!(matches!(hits.hit(3, 3), Some(HitTarget::DatasetRow(2))));
+}
+
+#[test]
+fn traffic_monitor() {
+    let view = TrafficView {
+        rows: vec![String::from("in  Ui/Pages"), String::from("out Ui/GetPage")],
+        highlight_index: 1,
+        detail: String::from("{}"),
+        filter: Some("ui"),
+        paused: true,
+        dropped: 3,
+    };
+    let mut renderer = headless(30, 10);
+    let hits = renderer.render_traffic(&view);
+    let screen = renderer.screen_text();
+    let lines: Vec<&str> = screen.lines().collect();
+    assert_eq!(lines[0], "╭Traffic (paused, 3 missed)──╮");
+    assert_eq!(lines[1], "│in  Ui/Pages                │");
+    assert_eq!(lines[2], "│out Ui/GetPage              │");
+    assert_eq!(lines[6], "╭Message─────────────────────╮");
+    assert_eq!(lines[7], "│{}                          │");
+    assert_eq!(lines[9], "/ui");
+    assert!(matches!(hits.hit(3, 2), Some(HitTarget::TrafficRow(1))));
+}
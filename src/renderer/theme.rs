@@ -0,0 +1,348 @@
+// Colors and styles used by the TUI renderer.
+//
+// Themes are chosen by name from the config. Built in themes are "dark" and "light" (16 colors),
+// "dark-truecolor" and "light-truecolor", and "monochrome", which is always used if NO_COLOR is set.
+// User themes start from a base theme and replace any of its styles:
+//
+// "theme": {
+//     "name": "mine",
+//     "themes": {
+//         "mine": { "base": "dark", "selected": { "fg": "black", "bg": "#ffaf00", "modifiers": ["bold"] } }
+//     }
+// }
+
+use std::{collections::HashMap, env};
+
+use serde::{Serialize, Deserialize};
+use tracing::warn;
+use tui::style::{Color, Modifier, Style};
+
+
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct ThemeConfig{
+    #[serde(default = "default_theme_name")]
+    pub name: String,
+    #[serde(default)]
+    pub themes: HashMap<String, CustomTheme>,
+}
+
+impl Default for ThemeConfig{
+    fn default() -> Self {
+        Self {
+            name: default_theme_name(),
+            themes: HashMap::new(),
+        }
+    }
+}
+
+fn default_theme_name() -> String {
+    "dark".into()
+}
+
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CustomTheme{
+    #[serde(default)]
+    pub base: Option<String>,
+    #[serde(flatten)]
+    pub styles: HashMap<String, ThemeStyle>,
+}
+
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct ThemeStyle{
+    #[serde(default)]
+    pub fg: Option<ThemeColor>,
+    #[serde(default)]
+    pub bg: Option<ThemeColor>,
+    #[serde(default)]
+    pub modifiers: Vec<ThemeModifier>,
+}
+
+impl ThemeStyle{
+    fn new(fg: Option<Color>, bg: Option<Color>, modifiers: Vec<ThemeModifier>) -> Self{
+        Self {
+            fg: fg.map(ThemeColor),
+            bg: bg.map(ThemeColor),
+            modifiers,
+        }
+    }
+
+    pub fn style(&self) -> Style{
+        let mut style = Style::default();
+        if let Some(fg) = &self.fg {
+            style = style.fg(fg.0);
+        }
+        if let Some(bg) = &self.bg {
+            style = style.bg(bg.0);
+        }
+        for modifier in self.modifiers.iter(){
+            style = style.add_modifier(modifier.modifier());
+        }
+        style
+    }
+}
+
+#[derive(Clone, Copy, Debug, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ThemeModifier{
+    Bold,
+    Dim,
+    Italic,
+    Underlined,
+    Reversed,
+}
+
+impl ThemeModifier{
+    fn modifier(&self) -> Modifier{
+        match self{
+            ThemeModifier::Bold => Modifier::BOLD,
+            ThemeModifier::Dim => Modifier::DIM,
+            ThemeModifier::Italic => Modifier::ITALIC,
+            ThemeModifier::Underlined => Modifier::UNDERLINED,
+            ThemeModifier::Reversed => Modifier::REVERSED,
+        }
+    }
+}
+
+// A color written as a name ("light-green"), an index into the 256 color palette ("208"),
+// or a hex rgb value ("#ff8700")
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub struct ThemeColor(pub Color);
+
+impl TryFrom<String> for ThemeColor{
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        let name = value.to_lowercase().replace(['-', '_', ' '], "");
+        let color = match name.as_str(){
+            "reset" | "default" => Color::Reset,
+            "black" => Color::Black,
+            "red" => Color::Red,
+            "green" => Color::Green,
+            "yellow" => Color::Yellow,
+            "blue" => Color::Blue,
+            "magenta" => Color::Magenta,
+            "cyan" => Color::Cyan,
+            "gray" | "grey" => Color::Gray,
+            "darkgray" | "darkgrey" => Color::DarkGray,
+            "lightred" => Color::LightRed,
+            "lightgreen" => Color::LightGreen,
+            "lightyellow" => Color::LightYellow,
+            "lightblue" => Color::LightBlue,
+            "lightmagenta" => Color::LightMagenta,
+            "lightcyan" => Color::LightCyan,
+            "white" => Color::White,
+            hex if hex.starts_with('#') && hex.len() == 7 => {
+                let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16);
+                match (channel(1), channel(3), channel(5)){
+                    (Ok(r), Ok(g), Ok(b)) => Color::Rgb(r, g, b),
+                    _ => return Err(format!("invalid hex color: {}", value)),
+                }
+            },
+            index => match index.parse::<u8>(){
+                Ok(i) => Color::Indexed(i),
+                Err(_) => return Err(format!("unknown color: {}", value)),
+            },
+        };
+        Ok(ThemeColor(color))
+    }
+}
+
+impl From<ThemeColor> for String{
+    fn from(color: ThemeColor) -> Self {
+        match color.0{
+            Color::Rgb(r, g, b) => format!("#{:02x}{:02x}{:02x}", r, g, b),
+            Color::Indexed(i) => i.to_string(),
+            Color::LightRed => "light-red".into(),
+            Color::LightGreen => "light-green".into(),
+            Color::LightYellow => "light-yellow".into(),
+            Color::LightBlue => "light-blue".into(),
+            Color::LightMagenta => "light-magenta".into(),
+            Color::LightCyan => "light-cyan".into(),
+            Color::DarkGray => "dark-gray".into(),
+            other => format!("{:?}", other).to_lowercase(),
+        }
+    }
+}
+
+
+#[derive(Clone, Debug)]
+pub struct Theme{
+    pub background: ThemeStyle,
+    pub text: ThemeStyle,
+    pub border: ThemeStyle,
+    pub title: ThemeStyle,
+    pub selected: ThemeStyle,
+    pub text_entry: ThemeStyle,
+    pub button: ThemeStyle,
+    pub list_highlight: ThemeStyle,
+    pub status_bar: ThemeStyle,
+    pub banner: ThemeStyle,
+    pub scrollbar: ThemeStyle,
+    pub filter_match: ThemeStyle,
+    pub changed: ThemeStyle,
+}
+
+impl Theme{
+    // the theme named in the config, falling back to the default if it cannot be found
+    pub fn from_config(config: &ThemeConfig) -> Self{
+        if no_color() {
+            return Self::monochrome();
+        }
+        match Self::named(config, &config.name, 0){
+            Some(theme) => theme,
+            None => {
+                warn!("Unknown theme {:?}, using the default", config.name);
+                Self::dark()
+            },
+        }
+    }
+
+    fn named(config: &ThemeConfig, name: &str, depth: usize) -> Option<Self>{
+        if let Some(custom) = config.themes.get(name) {
+            // limit depth to stop themes from being based on each other
+            let base = match (&custom.base, depth < 8){
+                (Some(base), true) => Self::named(config, base, depth + 1)?,
+                _ => Self::dark(),
+            };
+            let mut theme = base;
+            for (element, style) in custom.styles.iter(){
+                if !theme.set(element, style.clone()) {
+                    warn!("Unknown theme element {:?} in theme {:?}", element, name);
+                }
+            }
+            return Some(theme);
+        }
+        match name{
+            "dark" => Some(Self::dark()),
+            "light" => Some(Self::light()),
+            "dark-truecolor" => Some(Self::dark_truecolor()),
+            "light-truecolor" => Some(Self::light_truecolor()),
+            "monochrome" => Some(Self::monochrome()),
+            _ => None,
+        }
+    }
+
+    fn set(&mut self, element: &str, style: ThemeStyle) -> bool{
+        let field = match element{
+            "background" => &mut self.background,
+            "text" => &mut self.text,
+            "border" => &mut self.border,
+            "title" => &mut self.title,
+            "selected" => &mut self.selected,
+            "text_entry" => &mut self.text_entry,
+            "button" => &mut self.button,
+            "list_highlight" => &mut self.list_highlight,
+            "status_bar" => &mut self.status_bar,
+            "banner" => &mut self.banner,
+            "scrollbar" => &mut self.scrollbar,
+            "filter_match" => &mut self.filter_match,
+            "changed" => &mut self.changed,
+            _ => return false,
+        };
+        *field = style;
+        true
+    }
+
+    pub fn dark() -> Self{
+        Self {
+            background: ThemeStyle::new(None, Some(Color::Black), vec![]),
+            text: ThemeStyle::new(Some(Color::White), None, vec![]),
+            border: ThemeStyle::new(Some(Color::White), None, vec![]),
+            title: ThemeStyle::new(Some(Color::White), None, vec![ThemeModifier::Bold]),
+            selected: ThemeStyle::new(Some(Color::Black), Some(Color::LightGreen), vec![ThemeModifier::Bold]),
+            text_entry: ThemeStyle::new(Some(Color::Gray), None, vec![]),
+            button: ThemeStyle::new(Some(Color::LightCyan), None, vec![]),
+            list_highlight: ThemeStyle::new(Some(Color::Black), Some(Color::LightGreen), vec![ThemeModifier::Bold]),
+            status_bar: ThemeStyle::new(Some(Color::Black), Some(Color::Gray), vec![]),
+            banner: ThemeStyle::new(Some(Color::White), Some(Color::Red), vec![ThemeModifier::Bold]),
+            scrollbar: ThemeStyle::new(Some(Color::Gray), None, vec![]),
+            filter_match: ThemeStyle::new(None, None, vec![ThemeModifier::Bold, ThemeModifier::Underlined]),
+            changed: ThemeStyle::new(Some(Color::LightYellow), None, vec![ThemeModifier::Bold]),
+        }
+    }
+
+    pub fn light() -> Self{
+        Self {
+            background: ThemeStyle::new(None, Some(Color::White), vec![]),
+            text: ThemeStyle::new(Some(Color::Black), None, vec![]),
+            border: ThemeStyle::new(Some(Color::DarkGray), None, vec![]),
+            title: ThemeStyle::new(Some(Color::Black), None, vec![ThemeModifier::Bold]),
+            selected: ThemeStyle::new(Some(Color::White), Some(Color::Blue), vec![ThemeModifier::Bold]),
+            text_entry: ThemeStyle::new(Some(Color::DarkGray), None, vec![]),
+            button: ThemeStyle::new(Some(Color::Blue), None, vec![]),
+            list_highlight: ThemeStyle::new(Some(Color::White), Some(Color::Blue), vec![ThemeModifier::Bold]),
+            status_bar: ThemeStyle::new(Some(Color::White), Some(Color::DarkGray), vec![]),
+            banner: ThemeStyle::new(Some(Color::White), Some(Color::Red), vec![ThemeModifier::Bold]),
+            scrollbar: ThemeStyle::new(Some(Color::DarkGray), None, vec![]),
+            filter_match: ThemeStyle::new(None, None, vec![ThemeModifier::Bold, ThemeModifier::Underlined]),
+            changed: ThemeStyle::new(Some(Color::Magenta), None, vec![ThemeModifier::Bold]),
+        }
+    }
+
+    pub fn dark_truecolor() -> Self{
+        Self {
+            background: ThemeStyle::new(None, Some(Color::Rgb(0x1c, 0x1c, 0x1c)), vec![]),
+            text: ThemeStyle::new(Some(Color::Rgb(0xd0, 0xd0, 0xd0)), None, vec![]),
+            border: ThemeStyle::new(Some(Color::Rgb(0x6c, 0x6c, 0x6c)), None, vec![]),
+            title: ThemeStyle::new(Some(Color::Rgb(0xff, 0xff, 0xff)), None, vec![ThemeModifier::Bold]),
+            selected: ThemeStyle::new(Some(Color::Rgb(0x1c, 0x1c, 0x1c)), Some(Color::Rgb(0x87, 0xd7, 0x87)), vec![ThemeModifier::Bold]),
+            text_entry: ThemeStyle::new(Some(Color::Rgb(0xa8, 0xa8, 0xa8)), None, vec![]),
+            button: ThemeStyle::new(Some(Color::Rgb(0x87, 0xd7, 0xff)), None, vec![]),
+            list_highlight: ThemeStyle::new(Some(Color::Rgb(0x1c, 0x1c, 0x1c)), Some(Color::Rgb(0x87, 0xd7, 0x87)), vec![ThemeModifier::Bold]),
+            status_bar: ThemeStyle::new(Some(Color::Rgb(0xd0, 0xd0, 0xd0)), Some(Color::Rgb(0x3a, 0x3a, 0x3a)), vec![]),
+            banner: ThemeStyle::new(Some(Color::Rgb(0xff, 0xff, 0xff)), Some(Color::Rgb(0xaf, 0x00, 0x00)), vec![ThemeModifier::Bold]),
+            scrollbar: ThemeStyle::new(Some(Color::Rgb(0x6c, 0x6c, 0x6c)), None, vec![]),
+            filter_match: ThemeStyle::new(None, None, vec![ThemeModifier::Bold, ThemeModifier::Underlined]),
+            changed: ThemeStyle::new(Some(Color::Rgb(0xff, 0xd7, 0x5f)), None, vec![ThemeModifier::Bold]),
+        }
+    }
+
+    pub fn light_truecolor() -> Self{
+        Self {
+            background: ThemeStyle::new(None, Some(Color::Rgb(0xfa, 0xfa, 0xfa)), vec![]),
+            text: ThemeStyle::new(Some(Color::Rgb(0x26, 0x26, 0x26)), None, vec![]),
+            border: ThemeStyle::new(Some(Color::Rgb(0x8a, 0x8a, 0x8a)), None, vec![]),
+            title: ThemeStyle::new(Some(Color::Rgb(0x12, 0x12, 0x12)), None, vec![ThemeModifier::Bold]),
+            selected: ThemeStyle::new(Some(Color::Rgb(0xff, 0xff, 0xff)), Some(Color::Rgb(0x00, 0x5f, 0xaf)), vec![ThemeModifier::Bold]),
+            text_entry: ThemeStyle::new(Some(Color::Rgb(0x58, 0x58, 0x58)), None, vec![]),
+            button: ThemeStyle::new(Some(Color::Rgb(0x00, 0x5f, 0xaf)), None, vec![]),
+            list_highlight: ThemeStyle::new(Some(Color::Rgb(0xff, 0xff, 0xff)), Some(Color::Rgb(0x00, 0x5f, 0xaf)), vec![ThemeModifier::Bold]),
+            status_bar: ThemeStyle::new(Some(Color::Rgb(0x26, 0x26, 0x26)), Some(Color::Rgb(0xd0, 0xd0, 0xd0)), vec![]),
+            banner: ThemeStyle::new(Some(Color::Rgb(0xff, 0xff, 0xff)), Some(Color::Rgb(0xd7, 0x00, 0x00)), vec![ThemeModifier::Bold]),
+            scrollbar: ThemeStyle::new(Some(Color::Rgb(0x8a, 0x8a, 0x8a)), None, vec![]),
+            filter_match: ThemeStyle::new(None, None, vec![ThemeModifier::Bold, ThemeModifier::Underlined]),
+            changed: ThemeStyle::new(Some(Color::Rgb(0xaf, 0x5f, 0x00)), None, vec![ThemeModifier::Bold]),
+        }
+    }
+
+    // no colors at all, selection is shown by reversing the text
+    pub fn monochrome() -> Self{
+        let plain = ThemeStyle::default();
+        let reversed = ThemeStyle::new(None, None, vec![ThemeModifier::Reversed, ThemeModifier::Bold]);
+        Self {
+            background: plain.clone(),
+            text: plain.clone(),
+            border: plain.clone(),
+            title: ThemeStyle::new(None, None, vec![ThemeModifier::Bold]),
+            selected: reversed.clone(),
+            text_entry: plain.clone(),
+            button: plain.clone(),
+            list_highlight: reversed.clone(),
+            status_bar: ThemeStyle::new(None, None, vec![ThemeModifier::Reversed]),
+            banner: reversed,
+            scrollbar: plain,
+            filter_match: ThemeStyle::new(None, None, vec![ThemeModifier::Bold, ThemeModifier::Underlined]),
+            changed: ThemeStyle::new(None, None, vec![ThemeModifier::Bold]),
+        }
+    }
+}
+
+// https://no-color.org
+fn no_color() -> bool{
+    match env::var_os("NO_COLOR"){
+        Some(value) => !value.is_empty(),
+        None => false,
+    }
+}
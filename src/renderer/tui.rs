@@ -1,4 +1,4 @@
-use std::{io::{self, Stdout}, collections::HashMap};
+use std::{io::{self, Stdout}, collections::HashMap, panic};
 use crossterm::{
 	event::{
 		EnableMouseCapture,
@@ -12,136 +12,686 @@ use crossterm::{
 		EnterAlternateScreen,
 		LeaveAlternateScreen
 	},
+	cursor::Show,
 	execute
 };
 
 use spider_client::message::{UiPage, UiElement, DatasetData, AbsoluteDatasetPath, UiElementKind};
 use tui::{
 	Terminal,
-	widgets::{Block, Borders, Paragraph, BorderType, List, ListItem},
+	widgets::{Block, Borders, Paragraph, BorderType, List, ListItem, Wrap},
 	layout::{Layout, Direction, Constraint, Rect},
-	backend::Backend, Frame, style::{Color, Style, Modifier}, text::Text
+	backend::Backend, Frame, text::{Text, Span, Spans}
 };
 
-use tui::backend::CrosstermBackend;
+use chrono::Local;
+use tui::backend::{CrosstermBackend, TestBackend};
 
 
-use crate::model::processor::PageState;
+use crate::{model::{processor::{PageState, grid_dimensions, page_list::{PageListView, PageListEntry, PageSort}, split::{SplitDirection, SplitLayout}, datasets::InspectorRow, traffic::TrafficView}, update::{ConnectionState, Notice, Status}}, wizard::WizardScreen};
 
-use super::Renderer;
+use super::{Renderer, HitMap, HitTarget, Region, canvas::{Canvas, LayoutCache}, theme::Theme};
 
-pub struct TUI{
+
+pub struct TUI<B: Backend = CrosstermBackend<Stdout>>{
 	
-	term: Terminal<CrosstermBackend<Stdout>>,
+	term: Terminal<B>,
+	theme: Theme,
+	status: Status,
+	notice: Option<Notice>,
+	tabs: Vec<String>,
+	active_tab: usize,
+	owns_terminal: bool, // the terminal mode needs to be set up and restored
+	on_shutdown: Option<fn(&B)>, // given the final screen
 }
 
 impl TUI{
-	pub fn new() -> Self{
+	pub fn new(theme: Theme) -> io::Result<Self>{
 
 		let mut stdout = io::stdout();
-		execute!(
+		let setup = execute!(
 			stdout,
 			EnterAlternateScreen,
-			// EnableMouseCapture,
+			EnableMouseCapture,
 			EnableBracketedPaste
-		).unwrap();
+		);
+		if let Err(e) = setup {
+			restore_terminal();
+			return Err(e);
+		}
 		let backend = CrosstermBackend::new(stdout);
-		let terminal = Terminal::new(backend).expect("able to create a terminal");
+		let terminal = match Terminal::new(backend){
+			Ok(terminal) => terminal,
+			Err(e) => {
+				restore_terminal();
+				return Err(e);
+			},
+		};
+
+		Ok(Self {
+			term: terminal,
+			theme,
+			status: Status::default(),
+			notice: None,
+			tabs: Vec::new(),
+			active_tab: 0,
+			owns_terminal: true,
+			on_shutdown: None,
+		})
+	}
+}
+
+impl<B: Backend> TUI<B>{
+	// the first run setup, drawn before there is a model
+	pub fn render_wizard(&mut self, screen: &WizardScreen){
+		let theme = &self.theme;
+		self.term.draw(|frame|{
+			let b = Block::default()
+				.title(Span::styled(screen.title.as_str(), theme.title.style()))
+				.borders(Borders::all())
+				.border_style(theme.border.style())
+				.border_type(BorderType::Rounded)
+				.style(theme.background.style());
+			let inner = b.inner(frame.size());
+			frame.render_widget(b, frame.size());
+
+			let input_height = if screen.input.is_some() { 3 } else { 0 };
+			let constraints = vec![Constraint::Min(1), Constraint::Length(input_height), Constraint::Length(1), Constraint::Length(1)];
+			let areas = Layout::default()
+				.constraints(constraints)
+				.direction(Direction::Vertical)
+				.split(inner);
+
+			let w = Paragraph::new(screen.body.as_str())
+				.style(theme.text.style())
+				.wrap(Wrap { trim: false });
+			frame.render_widget(w, areas[0]);
+
+			if let Some((text, cursor)) = &screen.input {
+				let b = Block::default()
+					.borders(Borders::all())
+					.border_style(theme.selected.style());
+				let input_inner = b.inner(areas[1]);
+				// pasted text may contain line breaks, keep it on one line
+				let text: String = text.chars().map(|ch| if ch == '\n' { ' ' } else { ch }).collect();
+				let cursor = *cursor as u16;
+				let offset = cursor.saturating_sub(input_inner.width.saturating_sub(1));
+				let w = Paragraph::new(text)
+					.style(theme.text.style())
+					.scroll((0, offset))
+					.block(b);
+				frame.render_widget(w, areas[1]);
+				if input_inner.width > 0 && input_inner.height > 0 {
+					frame.set_cursor(input_inner.x + cursor - offset, input_inner.y);
+				}
+			}
+
+			if let Some(error) = &screen.error {
+				let w = Paragraph::new(error.as_str()).style(theme.banner.style());
+				frame.render_widget(w, areas[2]);
+			}
+			let w = Paragraph::new(screen.hint.as_str()).style(theme.status_bar.style());
+			frame.render_widget(w, areas[3]);
+		}).unwrap();
+	}
+}
+
+impl<B: Backend> TUI<B>{
+	// a list to choose from outside of the model, such as the profiles
+	pub fn render_choice_list(&mut self, title: &str, items: Vec<String>, highlight_index: usize) -> HitMap{
+		let theme = &self.theme;
+		let mut hits = HitMap::new();
+		self.term.draw(|frame|{
+			hits = draw_list(frame, theme, title, items, highlight_index, frame.size(), HitTarget::PageListItem);
+		}).unwrap();
+		hits
+	}
+}
 
+// put the terminal back how it was found, whatever state it is in
+pub fn restore_terminal(){
+	let _ = disable_raw_mode();
+	let _ = execute!(
+		io::stdout(),
+		DisableBracketedPaste,
+		DisableMouseCapture,
+		LeaveAlternateScreen,
+		Show,
+	);
+}
+
+// restore the terminal before a panic message is printed, otherwise it is lost with the alternate screen
+pub fn install_panic_hook(){
+	let default_hook = panic::take_hook();
+	panic::set_hook(Box::new(move |info| {
+		restore_terminal();
+		default_hook(info);
+	}));
+}
+
+impl<B: Backend> Drop for TUI<B>{
+	fn drop(&mut self) {
+		if self.owns_terminal {
+			restore_terminal();
+		}
+	}
+}
+
+impl TUI<TestBackend>{
+	// renders into memory instead of the terminal
+	pub fn headless(width: u16, height: u16, theme: Theme) -> Self{
+		let terminal = Terminal::new(TestBackend::new(width, height)).expect("able to create a test terminal");
 		Self {
-			term: terminal
+			term: terminal,
+			theme,
+			status: Status::default(),
+			notice: None,
+			tabs: Vec::new(),
+			active_tab: 0,
+			owns_terminal: false,
+			on_shutdown: None,
+		}
+	}
+
+	// print the last screen drawn when the renderer shuts down
+	pub fn print_on_shutdown(mut self) -> Self{
+		self.on_shutdown = Some(print_screen);
+		self
+	}
+
+	// the symbols drawn on the screen, one line per row with trailing spaces removed
+	#[cfg(test)]
+	pub fn screen_text(&self) -> String{
+		buffer_text(self.term.backend())
+	}
+}
+
+fn print_screen(backend: &TestBackend){
+	print!("{}", buffer_text(backend));
+}
+
+fn buffer_text(backend: &TestBackend) -> String{
+	let buffer = backend.buffer();
+	let mut text = String::new();
+	for y in 0..buffer.area.height{
+		let mut line = String::new();
+		for x in 0..buffer.area.width{
+			line.push_str(&buffer.get(x, y).symbol);
 		}
+		text.push_str(line.trim_end());
+		text.push('\n');
 	}
+	text
 }
 
-impl Renderer for TUI{
+impl<B: Backend + Send + Sync + 'static> Renderer for TUI<B>{
 	fn startup(&mut self) {
-		enable_raw_mode();
+		if self.owns_terminal {
+			enable_raw_mode();
+		}
 	}
 
-	fn render_menu(&mut self) {
-		todo!()
+	fn set_status(&mut self, status: &Status) {
+		self.status = status.clone();
 	}
 
-	fn render_page(&mut self, page: &UiPage, state: &PageState, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) {
+	fn set_notice(&mut self, notice: Option<&Notice>) {
+		self.notice = notice.cloned();
+	}
+
+	fn set_tabs(&mut self, tabs: &[String], active: usize) {
+		self.tabs = tabs.to_vec();
+		self.active_tab = active;
+	}
+
+	fn render_menu(&mut self, entries: &[&str], highlight_index: usize, details: &[String]) -> HitMap {
+		let status = &self.status;
+		let notice = &self.notice;
+		let theme = &self.theme;
+		let mut hits = HitMap::new();
 		self.term.draw(|frame|{
-			let constraints = vec![Constraint::Min(5), Constraint::Length(1)];
+			let size = frame.size();
+			let area = draw_connection_banner(frame, theme, &status.connection, size);
+			let area = draw_notice(frame, theme, notice, area);
+
+			let width = entries.iter().map(|entry| entry.len() as u16).max().unwrap_or(0) + 4;
 			let areas = Layout::default()
-				.constraints(constraints)
-				.direction(Direction::Vertical)
-				.split(frame.size());
+				.constraints(vec![Constraint::Length(width.max(12)), Constraint::Min(0)])
+				.direction(Direction::Horizontal)
+				.split(area);
+
+			let items = entries.iter().map(|entry| entry.to_string()).collect();
+			hits = draw_list(frame, theme, "Menu", items, highlight_index, areas[0], HitTarget::MenuItem);
 
 			let b = Block::default()
-				.title(format!("{} (esc=Menu)", page.name()))
-				.borders(Borders::TOP)
-				.border_style(Style::default().fg(Color::White))
-				.border_type(BorderType::Double)
-				.style(Style::default().bg(Color::Black));
-			let inner_size = b.inner(areas[0]);
-			frame.render_widget(b, areas[0]);
-
-			// debug area
-			let default = String::from("-");
-			let id_text = state.get_selected_id().unwrap_or(&default);
-			let indexes = state.get_selected_datasets();
-			let widget = Paragraph::new(format!("{} | {:?}", id_text, indexes));
-			frame.render_widget(widget, areas[1]);
-
-			draw_elem(frame, state, inner_size, page.root(), &None, data_map, &Vec::new());		
+				.borders(Borders::all())
+				.border_style(theme.border.style())
+				.border_type(BorderType::Rounded)
+				.style(theme.background.style());
+			let text = details.join("\n");
+			let w = Paragraph::new(text)
+				.style(theme.text.style())
+				.wrap(Wrap { trim: false })
+				.block(b);
+			frame.render_widget(w, areas[1]);
+		}).unwrap();
+		hits
+	}
+
+	fn render_page(&mut self, page: &UiPage, state: &mut PageState, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) -> HitMap {
+		let status = &self.status;
+		let notice = &self.notice;
+		let theme = &self.theme;
+		let tabs = &self.tabs;
+		let active_tab = self.active_tab;
+		let mut hits = HitMap::new();
+		self.term.draw(|frame|{
+			let mut tab_hits = HitMap::new();
+			let area = draw_page_frame(frame, theme, status, notice, tabs, active_tab, &mut tab_hits);
+			let (page_hits, cursor) = draw_page(frame, theme, page, state, data_map, page_block(theme, page, Borders::TOP, true), area);
+			if let Some((x, y)) = cursor {
+				frame.set_cursor(x, y);
+			}
+			hits = page_hits;
+			hits.extend(tab_hits);
+		}).unwrap();
+		hits
+	}
 
+	fn render_split(&mut self, panes: [(&UiPage, &mut PageState); 2], layout: &SplitLayout, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) -> HitMap {
+		let status = &self.status;
+		let notice = &self.notice;
+		let theme = &self.theme;
+		let tabs = &self.tabs;
+		let active_tab = self.active_tab;
+		let mut hits = HitMap::new();
+		self.term.draw(|frame|{
+			let mut tab_hits = HitMap::new();
+			let area = draw_page_frame(frame, theme, status, notice, tabs, active_tab, &mut tab_hits);
+			let (direction, second_borders) = match layout.direction{
+				SplitDirection::SideBySide => (Direction::Horizontal, Borders::TOP | Borders::LEFT),
+				SplitDirection::Stacked => (Direction::Vertical, Borders::TOP),
+			};
+			let ratio = layout.ratio.min(100);
+			let areas = Layout::default()
+				.constraints(vec![Constraint::Percentage(ratio), Constraint::Percentage(100 - ratio)])
+				.direction(direction)
+				.split(area);
+
+			for (i, (page, state)) in panes.into_iter().enumerate(){
+				let borders = if i == 0 { Borders::TOP } else { second_borders };
+				let pane = areas[i];
+				hits.insert(Region{ x: pane.x, y: pane.y, width: pane.width, height: pane.height }, HitTarget::Pane(i));
+				let focused = i == layout.focus;
+				let (page_hits, cursor) = draw_page(frame, theme, page, state, data_map, page_block(theme, page, borders, focused), pane);
+				if let (true, Some((x, y))) = (focused, cursor) {
+					frame.set_cursor(x, y);
+				}
+				hits.extend(page_hits);
+			}
+			hits.extend(tab_hits);
 		}).unwrap();
+		hits
 	}
 
-	fn render_page_list(&mut self, list: &Vec<&UiPage>, highlight_index: usize) {
+	fn render_page_list(&mut self, view: &PageListView) -> HitMap {
+		let status = &self.status;
+		let notice = &self.notice;
+		let theme = &self.theme;
+		let mut hits = HitMap::new();
 		self.term.draw(|frame|{
+			let size = frame.size();
+			let area = draw_connection_banner(frame, theme, &status.connection, size);
+			let mut area = draw_notice(frame, theme, notice, area);
+
+			// the filter goes along the bottom while it is being typed
+			if let Some(filter) = view.filter {
+				let areas = Layout::default()
+					.constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+					.direction(Direction::Vertical)
+					.split(area);
+				let w = Paragraph::new(format!("/{}", filter))
+					.style(theme.text.style());
+				frame.render_widget(w, areas[1]);
+				area = areas[0];
+			}
+
+			let items: Vec<Spans> = view.entries.iter().enumerate()
+				.map(|(i, entry)| page_list_item(theme, i, entry))
+				.collect();
+			let title = match view.sort{
+				PageSort::Base => String::from("Select Page (q=Quit)"),
+				sort => format!("Select Page (q=Quit) by {}", sort.label()),
+			};
+			hits = draw_list(frame, theme, &title, items, view.highlight_index, area, HitTarget::PageListItem);
+		}).unwrap();
+		hits
+	}
+
+	fn render_datasets(&mut self, rows: &[InspectorRow], highlight_index: usize) -> HitMap {
+		let status = &self.status;
+		let notice = &self.notice;
+		let theme = &self.theme;
+		let mut hits = HitMap::new();
+		self.term.draw(|frame|{
+			let size = frame.size();
+			let area = draw_connection_banner(frame, theme, &status.connection, size);
+			let area = draw_notice(frame, theme, notice, area);
+
+			let items: Vec<Spans> = rows.iter().map(|row| {
+				let marker = match (row.expandable, row.expanded){
+					(true, true) => "▾ ",
+					(true, false) => "▸ ",
+					(false, _) => "  ",
+				};
+				let text = format!("{}{}{}", "  ".repeat(row.depth), marker, row.text);
+				if row.changed {
+					Spans::from(Span::styled(text, theme.changed.style()))
+				}else{
+					Spans::from(text)
+				}
+			}).collect();
+			let title = if rows.is_empty() { "Datasets (none received)" } else { "Datasets" };
+			hits = draw_list(frame, theme, title, items, highlight_index, area, HitTarget::DatasetRow);
+		}).unwrap();
+		hits
+	}
+
+	fn render_traffic(&mut self, view: &TrafficView) -> HitMap {
+		let status = &self.status;
+		let notice = &self.notice;
+		let theme = &self.theme;
+		let mut hits = HitMap::new();
+		self.term.draw(|frame|{
+			let size = frame.size();
+			let area = draw_connection_banner(frame, theme, &status.connection, size);
+			let area = draw_notice(frame, theme, notice, area);
+
+			// the messages above, the selected one below, and the filter along the bottom while it is being typed
+			let filter_height = if view.filter.is_some() { 1 } else { 0 };
+			let areas = Layout::default()
+				.constraints(vec![Constraint::Percentage(60), Constraint::Min(0), Constraint::Length(filter_height)])
+				.direction(Direction::Vertical)
+				.split(area);
+			if let Some(filter) = view.filter {
+				let w = Paragraph::new(format!("/{}", filter))
+					.style(theme.text.style());
+				frame.render_widget(w, areas[2]);
+			}
+
+			let title = match (view.paused, view.dropped){
+				(false, _) => String::from("Traffic"),
+				(true, 0) => String::from("Traffic (paused)"),
+				(true, dropped) => format!("Traffic (paused, {} missed)", dropped),
+			};
+			hits = draw_list(frame, theme, &title, view.rows.clone(), view.highlight_index, areas[0], HitTarget::TrafficRow);
 
 			let b = Block::default()
-				.title("Select Page (q=Quit)")
+				.title(Span::styled("Message", theme.title.style()))
 				.borders(Borders::all())
-				.border_style(Style::default().fg(Color::White))
+				.border_style(theme.border.style())
 				.border_type(BorderType::Rounded)
-				.style(Style::default().bg(Color::Black));
+				.style(theme.background.style());
+			let w = Paragraph::new(view.detail.as_str())
+				.block(b)
+				.style(theme.text.style())
+				.wrap(Wrap { trim: false });
+			frame.render_widget(w, areas[1]);
+		}).unwrap();
+		hits
+	}
 
-			// frame.render_widget(w, frame.size());
+	fn shutdown(self) {
+		if let Some(on_shutdown) = self.on_shutdown {
+			on_shutdown(self.term.backend());
+		}
+		// cleanup happens when dropped, so that it also happens on errors
+		drop(self);
+	}
 
-			let mut list_items = Vec::new();
-			for (i, item) in list.iter().enumerate(){
-				let mut list_item: ListItem =  ListItem::new(item.name().clone());
-				if i == highlight_index{
-					let style = Style::default()
-						.bg(Color::LightGreen)
-						.add_modifier(Modifier::BOLD);
 
-					list_item = list_item.style(style);
-				}
-				list_items.push(list_item);
+
+
+}
+
+
+// draws a line describing the connection if it is not connected, returns the remaining area
+fn draw_connection_banner<B: Backend>(frame: &mut Frame<B>, theme: &Theme, connection: &ConnectionState, rect: Rect) -> Rect{
+	let text = match connection{
+		ConnectionState::Connected => return rect,
+		ConnectionState::Connecting => String::from("Disconnected from base, reconnecting..."),
+		ConnectionState::Reconnecting(secs) => format!("Disconnected from base, reconnecting in {}s", secs),
+	};
+	let areas = Layout::default()
+		.constraints(vec![Constraint::Length(1), Constraint::Min(0)])
+		.direction(Direction::Vertical)
+		.split(rect);
+	let w = Paragraph::new(text)
+		.style(theme.banner.style());
+	frame.render_widget(w, areas[0]);
+	areas[1]
+}
+
+// draws everything around the pages: banner, tabs, notice, debug line and status bar.
+// Returns the area left for the pages
+fn draw_page_frame<B: Backend>(frame: &mut Frame<B>, theme: &Theme, status: &Status, notice: &Option<Notice>, tabs: &[String], active_tab: usize, tab_hits: &mut HitMap) -> Rect{
+	let size = frame.size();
+	let area = draw_connection_banner(frame, theme, &status.connection, size);
+	let area = draw_tab_bar(frame, theme, tabs, active_tab, area, tab_hits);
+	let area = draw_notice(frame, theme, notice, area);
+	let debug_height = if status.debug.is_some() { 1 } else { 0 };
+	let constraints = vec![Constraint::Min(5), Constraint::Length(debug_height), Constraint::Length(1)];
+	let areas = Layout::default()
+		.constraints(constraints)
+		.direction(Direction::Vertical)
+		.split(area);
+
+	if let Some(debug) = &status.debug {
+		let widget = Paragraph::new(debug.as_str())
+			.style(theme.status_bar.style());
+		frame.render_widget(widget, areas[1]);
+	}
+	draw_status_bar(frame, theme, status, areas[2]);
+	areas[0]
+}
+
+// the title and border around a page, the title is dimmed unless the page has focus
+fn page_block<'a>(theme: &Theme, page: &'a UiPage, borders: Borders, focused: bool) -> Block<'a>{
+	let title_style = if focused { theme.title.style() } else { theme.text.style() };
+	Block::default()
+		.title(Span::styled(page.name().as_str(), title_style))
+		.borders(borders)
+		.border_style(theme.border.style())
+		.border_type(BorderType::Double)
+		.style(theme.background.style())
+}
+
+// draws a page inside its block, returns where its elements can be clicked and where the text cursor is
+fn draw_page<B: Backend>(frame: &mut Frame<B>, theme: &Theme, page: &UiPage, state: &mut PageState, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, b: Block, area: Rect) -> (HitMap, Option<(u16, u16)>){
+	let mut inner_size = b.inner(area);
+	frame.render_widget(b, area);
+
+	// make room for a scrollbar if the page does not fit
+	// sizes are only kept for this frame, the pages and data may have moved by the next
+	let mut layout = LayoutCache::default();
+	let page_height = elem_calc_height(page.root(), &None, data_map, &mut layout);
+	let scrollbar_area = if page_height > inner_size.height && inner_size.width > 1 {
+		inner_size.width -= 1;
+		Some(Rect{ x: inner_size.right(), width: 1, ..inner_size })
+	}else{
+		None
+	};
+	state.set_viewport(inner_size.height, page_height);
+
+	let mut canvas = Canvas::new(inner_size, state.get_scroll(), theme).with_layout(layout);
+	let page_area = canvas.page_area(page_height);
+	draw_elem(&mut canvas, state, page_area, page.root(), &None, data_map, &Vec::new());
+	// keep the selected element in view, redrawing if that had to scroll
+	if state.take_follow_selection() {
+		if let Some(selected) = canvas.get_selected() {
+			let scroll = state.get_scroll();
+			state.scroll_to_show(selected.top(), selected.bottom());
+			if scroll != state.get_scroll() {
+				let layout = canvas.take_layout();
+				canvas = Canvas::new(inner_size, state.get_scroll(), theme).with_layout(layout);
+				draw_elem(&mut canvas, state, page_area, page.root(), &None, data_map, &Vec::new());
 			}
-			let list = List::new(list_items).block(b);
-			
-			frame.render_widget(list, frame.size());
-			
-		}).unwrap();
+		}
+	}
+	if let Some(scrollbar_area) = scrollbar_area {
+		draw_scrollbar(frame, theme, scrollbar_area, state.get_scroll(), page_height);
 	}
 
-	fn shutdown(mut self) {
-		// cleanup
-		disable_raw_mode();
-		execute!(
-			self.term.backend_mut(),
-			DisableBracketedPaste,
-			// DisableMouseCapture,
-			LeaveAlternateScreen,
-		).unwrap();
-		//terminal.show_cursor();
+	let cursor = canvas.screen_cursor();
+	let hits = canvas.take_hits();
+	frame.render_widget(canvas, inner_size);
+	(hits, cursor)
+}
+
+// draws the open tabs along the top if there is more than one, returns the remaining area
+fn draw_tab_bar<B: Backend>(frame: &mut Frame<B>, theme: &Theme, tabs: &[String], active: usize, rect: Rect, hits: &mut HitMap) -> Rect{
+	if tabs.len() < 2 {
+		return rect;
+	}
+	let areas = Layout::default()
+		.constraints(vec![Constraint::Length(1), Constraint::Min(0)])
+		.direction(Direction::Vertical)
+		.split(rect);
+	let mut spans = Vec::new();
+	let mut x = areas[0].x;
+	for (i, name) in tabs.iter().enumerate(){
+		let label = format!(" {} {} ", i + 1, name);
+		let width = label.chars().count() as u16;
+		let visible = width.min(areas[0].right().saturating_sub(x));
+		if visible > 0 {
+			hits.insert(Region{ x, y: areas[0].y, width: visible, height: 1 }, HitTarget::Tab(i));
+		}
+		let style = if i == active { theme.list_highlight.style() } else { theme.status_bar.style() };
+		spans.push(Span::styled(label, style));
+		x = x.saturating_add(width);
 	}
+	let w = Paragraph::new(Spans::from(spans))
+		.style(theme.status_bar.style());
+	frame.render_widget(w, areas[0]);
+	areas[1]
+}
 
+// draws the status segments on the left and the key hints on the right
+fn draw_status_bar<B: Backend>(frame: &mut Frame<B>, theme: &Theme, status: &Status, rect: Rect){
+	let hints: Vec<String> = status.hints.iter().map(|(key, description)| format!("{} {}", key, description)).collect();
+	let hints = hints.join("  ");
+	let hints_width = (hints.chars().count() as u16).min(rect.width);
+	let areas = Layout::default()
+		.constraints(vec![Constraint::Min(0), Constraint::Length(hints_width)])
+		.direction(Direction::Horizontal)
+		.split(rect);
+	let w = Paragraph::new(status.segments.join(" | "))
+		.style(theme.status_bar.style());
+	frame.render_widget(w, areas[0]);
+	let w = Paragraph::new(hints)
+		.style(theme.status_bar.style());
+	frame.render_widget(w, areas[1]);
+}
 
+// draws the latest notice along the bottom while it is recent, returns the remaining area
+fn draw_notice<B: Backend>(frame: &mut Frame<B>, theme: &Theme, notice: &Option<Notice>, rect: Rect) -> Rect{
+	let notice = match notice{
+		Some(notice) if Local::now() < notice.expires() => notice,
+		_ => return rect,
+	};
+	let areas = Layout::default()
+		.constraints(vec![Constraint::Min(0), Constraint::Length(1)])
+		.direction(Direction::Vertical)
+		.split(rect);
+	let w = Paragraph::new(format!("[{}] {}", notice.time.format("%H:%M:%S"), notice.text))
+		.style(theme.status_bar.style());
+	frame.render_widget(w, areas[1]);
+	areas[0]
+}
+
+// a page's name, numbered if it can be jumped to, with the chars matching the filter marked
+fn page_list_item<'a>(theme: &Theme, position: usize, entry: &'a PageListEntry) -> Spans<'a>{
+	let number = match position{
+		0..=8 => format!("{} ", position + 1),
+		_ => String::from("  "),
+	};
+	let mut spans = vec![Span::raw(number)];
+	let mut run = String::new();
+	let mut run_matches = false;
+	for (i, ch) in entry.page.name().chars().enumerate(){
+		let matches = entry.matches.contains(&i);
+		if matches != run_matches && !run.is_empty() {
+			spans.push(filter_span(theme, std::mem::take(&mut run), run_matches));
+		}
+		run_matches = matches;
+		run.push(ch);
+	}
+	if !run.is_empty() {
+		spans.push(filter_span(theme, run, run_matches));
+	}
+	Spans::from(spans)
+}
 
+fn filter_span(theme: &Theme, text: String, matches: bool) -> Span<'static>{
+	if matches {
+		Span::styled(text, theme.filter_match.style())
+	}else{
+		Span::raw(text)
+	}
+}
 
+// draws a bordered list of choices, returns where each choice can be clicked
+fn draw_list<'a, B: Backend, T: Into<Text<'a>>>(frame: &mut Frame<B>, theme: &Theme, title: &str, items: Vec<T>, highlight_index: usize, area: Rect, target: fn(usize) -> HitTarget) -> HitMap{
+	let mut hits = HitMap::new();
+	let b = Block::default()
+		.title(Span::styled(title, theme.title.style()))
+		.borders(Borders::all())
+		.border_style(theme.border.style())
+		.border_type(BorderType::Rounded)
+		.style(theme.background.style());
+
+	// scroll far enough to show the highlighted item, each item takes one row inside the border
+	let inner = b.inner(area);
+	let offset = highlight_index.saturating_sub((inner.height as usize).saturating_sub(1));
+	let mut list_items = Vec::new();
+	for (i, item) in items.into_iter().enumerate().skip(offset){
+		let mut list_item: ListItem = ListItem::new(item).style(theme.text.style());
+		if i == highlight_index{
+			list_item = list_item.style(theme.list_highlight.style());
+		}
+		list_items.push(list_item);
+	}
+	for i in 0..list_items.len().min(inner.height as usize){
+		let region = Region{ x: inner.x, y: inner.y + i as u16, width: inner.width, height: 1 };
+		hits.insert(region, target(offset + i));
+	}
+	let list = List::new(list_items).block(b);
+	frame.render_widget(list, area);
+	hits
 }
 
+// draws a scrollbar down the given column showing which part of the page is visible
+fn draw_scrollbar<B: Backend>(frame: &mut Frame<B>, theme: &Theme, rect: Rect, scroll: u16, page_height: u16){
+	let height = rect.height as u32;
+	let page_height = (page_height as u32).max(1);
+	let thumb_height = (height * height / page_height).clamp(1, height);
+	let max_scroll = page_height.saturating_sub(height).max(1);
+	let thumb_top = (scroll as u32).min(max_scroll) * (height - thumb_height) / max_scroll;
+	let mut bar = String::new();
+	for row in 0..height {
+		if row > 0 {
+			bar.push('\n');
+		}
+		if row >= thumb_top && row < thumb_top + thumb_height {
+			bar.push('█');
+		}else{
+			bar.push('│');
+		}
+	}
+	frame.render_widget(Paragraph::new(bar).style(theme.scrollbar.style()), rect);
+}
 
-fn draw_elem<B: Backend>(frame: &mut Frame<B>, state: &PageState, rect: Rect, elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, dataset_indices: &Vec<usize>){
+fn draw_elem(canvas: &mut Canvas, state: &PageState, rect: Rect, elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, dataset_indices: &Vec<usize>){
 	let content = match data {
 		Some(data) => elem.render_content(data),
 		None => elem.text(),
@@ -160,7 +710,7 @@ fn draw_elem<B: Backend>(frame: &mut Frame<B>, state: &PageState, rect: Rect, el
 				if let UiElementKind::Spacer = child.kind(){
 					constraints.push(Constraint::Min(0))
 				}else{
-					constraints.push(Constraint::Length(elem_calc_width(child, &datum, data_map)));
+					constraints.push(Constraint::Length(elem_calc_width(child, &datum, data_map, canvas.layout())));
 				}
 			}
 			let areas = Layout::default()
@@ -180,7 +730,7 @@ fn draw_elem<B: Backend>(frame: &mut Frame<B>, state: &PageState, rect: Rect, el
 					},
 					None => dataset_indices,
 				};
-				draw_elem(frame, state, *area, child, &datum, data_map, child_dataset_indices);
+				draw_elem(canvas, state, *area, child, &datum, data_map, child_dataset_indices);
 			}
 		},
 		spider_client::message::UiElementKind::Rows => {
@@ -190,7 +740,7 @@ fn draw_elem<B: Backend>(frame: &mut Frame<B>, state: &PageState, rect: Rect, el
 				if let UiElementKind::Spacer = child.kind(){
 					constraints.push(Constraint::Min(0))
 				}else{
-					constraints.push(Constraint::Length(elem_calc_height(child, &datum, data_map)));
+					constraints.push(Constraint::Length(elem_calc_height(child, &datum, data_map, canvas.layout())));
 				}
 			}
 			constraints.push(Constraint::Min(0));
@@ -211,21 +761,63 @@ fn draw_elem<B: Backend>(frame: &mut Frame<B>, state: &PageState, rect: Rect, el
 					},
 					None => dataset_indices,
 				};
-				draw_elem(frame, state, *area, child, &datum, data_map, child_dataset_indices);
+				draw_elem(canvas, state, *area, child, &datum, data_map, child_dataset_indices);
+			}
+		},
+		spider_client::message::UiElementKind::Grid(columns, rows) => {
+			let children: Vec<_> = elem.children_dataset(data, data_map).collect();
+			let (columns, _) = grid_dimensions(columns, rows, children.len());
+			// each row is as tall as its tallest cell
+			let mut constraints = Vec::new();
+			for row in children.chunks(columns){
+				let mut height = 0;
+				for (_, child, datum) in row{
+					let child_height = elem_calc_height(child, datum, data_map, canvas.layout());
+					if child_height > height{
+						height = child_height;
+					}
+				}
+				constraints.push(Constraint::Length(height));
+			}
+			constraints.push(Constraint::Min(0));
+			let row_areas = Layout::default()
+				.constraints(constraints)
+				.direction(Direction::Vertical)
+				.split(rect);
+			// columns share the width evenly
+			let column_constraints = vec![Constraint::Ratio(1, columns as u32); columns];
+			// render children
+			let mut v: Vec<usize>;
+			for (row_index, row) in children.chunks(columns).enumerate(){
+				let areas = Layout::default()
+					.constraints(column_constraints.clone())
+					.direction(Direction::Horizontal)
+					.split(row_areas[row_index]);
+				for ((cdi, child, datum), area) in row.iter().zip(areas.iter()){
+					let child_dataset_indices = match cdi{
+						Some(cdi) => {
+							v = dataset_indices.clone();
+							v.push(*cdi);
+							&v
+						},
+						None => dataset_indices,
+					};
+					draw_elem(canvas, state, *area, child, datum, data_map, child_dataset_indices);
+				}
 			}
 		},
-		spider_client::message::UiElementKind::Grid(_, _) => todo!(),
 		spider_client::message::UiElementKind::Text => {
-			let mut w = Paragraph::new(content);
+			let mut w = Paragraph::new(content).style(canvas.theme().text.style());
 			w = w.wrap(tui::widgets::Wrap { trim: false });
-			frame.render_widget(w, rect);
+			canvas.render_widget(w, rect);
 		},
 		spider_client::message::UiElementKind::TextEntry => {
-			let b = Block::default()
+			let theme = canvas.theme();
+			let selected_style = theme.selected.style();
+			let mut b = Block::default()
 				.title(content)
 				.borders(Borders::all())
-				// .border_style(Style::default().fg(Color::White))
-				.style(Style::default().bg(Color::Black));
+				.border_style(theme.text_entry.style());
 			let input_text = match elem.id(){
 				Some(id) => {
 					match state.get_uncommited_input(id, dataset_indices) {
@@ -235,29 +827,69 @@ fn draw_elem<B: Backend>(frame: &mut Frame<B>, state: &PageState, rect: Rect, el
 				},
 				None => "",
 			};
-			let mut w = Paragraph::new(input_text);
+			let inner = b.inner(rect);
+			let mut w = Paragraph::new(input_text).style(theme.text.style());
 			if state.get_selected_id() == elem.id() && state.get_selected_datasets() == dataset_indices{
-				w = w.style(Style::default().add_modifier(Modifier::BOLD));
+				w = w.style(selected_style);
+				b = b.border_style(selected_style);
+				canvas.set_selected(rect);
+				if let Some(id) = elem.id(){
+					// scroll the text so that the cursor stays inside the block
+					let cursor = state.get_input_cursor(id, dataset_indices) as u16;
+					let offset = cursor.saturating_sub(inner.width.saturating_sub(1));
+					w = w.scroll((0, offset));
+					if inner.width > 0 && inner.height > 0 {
+						canvas.set_cursor(inner.x + cursor - offset, inner.y);
+					}
+				}
 			}
-			frame.render_widget(w.block(b), rect);
+			add_element_hit(canvas, rect, elem, data, dataset_indices);
+			canvas.render_widget(w.block(b), rect);
 		},
 		spider_client::message::UiElementKind::Button => {
 			let b = Block::default().borders(Borders::ALL);
-			let mut w = Paragraph::new(content);
+			let mut w = Paragraph::new(content).style(canvas.theme().button.style());
 			if state.get_selected_id() == elem.id() && state.get_selected_datasets() == dataset_indices{
-				w = w.style(Style::default().add_modifier(Modifier::BOLD));
+				w = w.style(canvas.theme().selected.style());
+				canvas.set_selected(rect);
 			}
-			frame.render_widget(w.block(b), rect);
+			add_element_hit(canvas, rect, elem, data, dataset_indices);
+			canvas.render_widget(w.block(b), rect);
 		},
 		UiElementKind::Variable(content_part) => { // If part could not have been resolved
-			let w = Paragraph::new("e".to_owned() + &content_part.to_string());
-			frame.render_widget(w, rect);
+			let w = Paragraph::new("e".to_owned() + &content_part.to_string()).style(canvas.theme().text.style());
+			canvas.render_widget(w, rect);
 		} 
 	}
 }
 
 
-fn elem_calc_height(elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) -> u16{
+// make a selectable element clickable
+fn add_element_hit(canvas: &mut Canvas, rect: Rect, elem: &UiElement, data: &Option<&DatasetData>, dataset_indices: &Vec<usize>){
+	if !elem.selectable() {
+		return;
+	}
+	if let Some(id) = elem.id(){
+		let target = HitTarget::Element{
+			id: id.clone(),
+			dataset_indices: dataset_indices.clone(),
+			datum: data.cloned(),
+		};
+		canvas.add_hit(rect, target);
+	}
+}
+
+// the height of an element, measured once per frame
+fn elem_calc_height(elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, layout: &mut LayoutCache) -> u16{
+	if let Some(height) = layout.height(elem, data) {
+		return height;
+	}
+	let height = measure_height(elem, data, data_map, layout);
+	layout.set_height(elem, data, height);
+	height
+}
+
+fn measure_height(elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, layout: &mut LayoutCache) -> u16{
 	let mut elem_kind = elem.kind().clone();
 	elem_kind = elem_kind.resolve(data);
 
@@ -267,7 +899,7 @@ fn elem_calc_height(elem: &UiElement, data: &Option<&DatasetData>, data_map: &Ha
 		spider_client::message::UiElementKind::Columns => {
 			let mut height = 0;
 			for (_, child, data) in elem.children_dataset(data, data_map){
-				let child_height = elem_calc_height(child, &data, data_map);
+				let child_height = elem_calc_height(child, &data, data_map, layout);
 				if child_height > height{
 					height = child_height; 
 				}
@@ -277,11 +909,26 @@ fn elem_calc_height(elem: &UiElement, data: &Option<&DatasetData>, data_map: &Ha
 		spider_client::message::UiElementKind::Rows => {
 			let mut height = 0;
 			for (_, child, data) in elem.children_dataset(data, data_map){
-				height += elem_calc_height(child, &data, data_map);
+				height += elem_calc_height(child, &data, data_map, layout);
+			}
+			height
+		},
+		spider_client::message::UiElementKind::Grid(columns, rows) => {
+			let children: Vec<_> = elem.children_dataset(data, data_map).collect();
+			let (columns, _) = grid_dimensions(columns, rows, children.len());
+			let mut height = 0;
+			for row in children.chunks(columns){
+				let mut row_height = 0;
+				for (_, child, data) in row{
+					let child_height = elem_calc_height(child, data, data_map, layout);
+					if child_height > row_height{
+						row_height = child_height;
+					}
+				}
+				height += row_height;
 			}
 			height
 		},
-		spider_client::message::UiElementKind::Grid(_, _) => todo!(),
 		spider_client::message::UiElementKind::Text => {
 			let t = elem.render_content_opt(data);
 			// let text = Text::from(t);
@@ -295,7 +942,17 @@ fn elem_calc_height(elem: &UiElement, data: &Option<&DatasetData>, data_map: &Ha
 	}
 }
 
-fn elem_calc_width(elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>) -> u16{
+// the width of an element, measured once per frame
+fn elem_calc_width(elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, layout: &mut LayoutCache) -> u16{
+	if let Some(width) = layout.width(elem, data) {
+		return width;
+	}
+	let width = measure_width(elem, data, data_map, layout);
+	layout.set_width(elem, data, width);
+	width
+}
+
+fn measure_width(elem: &UiElement, data: &Option<&DatasetData>, data_map: &HashMap<AbsoluteDatasetPath, Vec<DatasetData>>, layout: &mut LayoutCache) -> u16{
 	let mut elem_kind = elem.kind().clone();
 	elem_kind = elem_kind.resolve(data);
 
@@ -305,21 +962,33 @@ fn elem_calc_width(elem: &UiElement, data: &Option<&DatasetData>, data_map: &Has
 		spider_client::message::UiElementKind::Columns => {
 			let mut width = 0;
 			for (_, child, data) in elem.children_dataset(data, data_map){
-				width += elem_calc_width(child, &data, data_map);
+				width += elem_calc_width(child, &data, data_map, layout);
 			}
 			width
 		},
 		spider_client::message::UiElementKind::Rows => {
 			let mut width = 0;
 			for (_, child, data) in elem.children_dataset(data, data_map){
-				let child_width = elem_calc_width(child, &data, data_map);
+				let child_width = elem_calc_width(child, &data, data_map, layout);
 				if child_width > width{
 					width = child_width; 
 				}
 			}
 			width
 		},
-		spider_client::message::UiElementKind::Grid(_, _) => todo!(),
+		spider_client::message::UiElementKind::Grid(columns, rows) => {
+			let children: Vec<_> = elem.children_dataset(data, data_map).collect();
+			let (columns, _) = grid_dimensions(columns, rows, children.len());
+			// columns are drawn evenly, so each is as wide as the widest cell
+			let mut cell_width = 0;
+			for (_, child, data) in children.iter(){
+				let child_width = elem_calc_width(child, data, data_map, layout);
+				if child_width > cell_width{
+					cell_width = child_width;
+				}
+			}
+			cell_width * columns as u16
+		},
 		spider_client::message::UiElementKind::Text => {
 			let text = Text::from(elem.render_content_opt(data));
 			TryInto::<u16>::try_into(text.width()).unwrap()
@@ -0,0 +1,108 @@
+// Drive the model from a recorded session instead of a base, to see exactly what a user saw.
+// Updates are fed at the pace they were recorded, scaled by the speed. In the terminal the
+// keyboard takes over once the session has played, headless the final screen is printed.
+
+use std::path::Path;
+
+use crossterm::event::Event;
+use spider_client::SpiderClient;
+use tokio::{select, sync::mpsc::Receiver, time::{sleep_until, Duration, Instant}};
+use tracing::info;
+
+use crate::{
+    config::{SpiderTuiConfig, Profile},
+    error::StartupError,
+    event_stream,
+    model::{Model, ModelExit, session::{load_session, RecordedUpdate}, update::ModelUpdate},
+    renderer::{theme::Theme, tui::{self, TUI}},
+};
+
+
+
+pub async fn run(config: SpiderTuiConfig, path: &Path, speed: f64, headless: Option<(u16, u16)>) -> Result<(), StartupError> {
+    let (header, updates) = load_session(path)?;
+    info!("Replaying {} updates from {:?}", updates.len(), path);
+
+    // the config the session was recorded with, so that it looks the same
+    let mut config = header.config.clone().unwrap_or(config);
+    // replaying does not record over the session
+    config.record_path = None;
+    let profile = config.profiles().into_iter()
+        .find(|profile| profile.name == header.profile)
+        .unwrap_or_else(|| Profile {
+            name: header.profile.clone(),
+            state_data_path: config.state_data_path.clone(),
+            keyfile_path: config.keyfile_path.clone(),
+            addresses: Vec::new(),
+        });
+    // a throwaway identity, nothing is sent anywhere
+    let relation = SpiderClient::new().self_relation();
+    let theme = Theme::from_config(&config.theme);
+
+    let exit = match headless{
+        Some((width, height)) => {
+            let renderer = TUI::headless(width, height, theme).print_on_shutdown();
+            let model = Model::replay(renderer, relation, &config, &profile, &header).await;
+            play(model, updates, speed, None).await
+        },
+        None => {
+            tui::install_panic_hook();
+            let mut events = event_stream::get_event_stream();
+            let renderer = TUI::new(theme).map_err(StartupError::Terminal)?;
+            let model = Model::replay(renderer, relation, &config, &profile, &header).await;
+            play(model, updates, speed, Some(&mut events)).await
+        },
+    };
+    info!("Replay ended with {:?}", exit);
+    Ok(())
+}
+
+async fn play(mut model: Model, updates: Vec<RecordedUpdate>, speed: f64, events: Option<&mut Receiver<Event>>) -> ModelExit {
+    let start = Instant::now();
+    for recorded in updates {
+        let due = if speed > 0.0 {
+            start + Duration::from_secs_f64(recorded.millis as f64 / 1000.0 / speed)
+        }else{
+            start
+        };
+        // the model's messages have nowhere to go, but must not be left to fill the channel
+        loop {
+            select! {
+                biased;
+                from_model = model.recv() => {
+                    if from_model.is_none() {
+                        return model.finish().await; // the model quit during the session
+                    }
+                }
+                _ = sleep_until(due) => break,
+            }
+        }
+        if model.send(recorded.update).await.is_err() {
+            return model.finish().await;
+        }
+    }
+
+    if let Some(events) = events {
+        info!("Replay finished, the keyboard takes over");
+        loop {
+            select! {
+                event = events.recv() => {
+                    match event{
+                        Some(event) => {
+                            if model.send(ModelUpdate::Event(event)).await.is_err() {
+                                break;
+                            }
+                        },
+                        None => break,
+                    }
+                }
+                from_model = model.recv() => {
+                    if from_model.is_none() {
+                        break;
+                    }
+                }
+            }
+        }
+    }
+    model.finish().await
+}
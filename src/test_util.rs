@@ -0,0 +1,57 @@
+// Builders for pages used by the tests
+
+use std::sync::OnceLock;
+
+use spider_client::{
+    message::{UiElement, UiElementKind, UiPageManager, UiPath},
+    SpiderClient, SpiderId2048,
+};
+
+// generating an id makes a new key, so the tests share one
+pub fn test_id() -> SpiderId2048 {
+    static ID: OnceLock<SpiderId2048> = OnceLock::new();
+    ID.get_or_init(|| SpiderClient::new().self_relation().id).clone()
+}
+
+// a page with the given root kind and children
+pub fn page(name: &str, kind: UiElementKind, children: Vec<UiElement>) -> UiPageManager {
+    let mut mgr = UiPageManager::new(test_id(), name);
+    let mut root = mgr
+        .get_element_mut(&UiPath::root())
+        .expect("all pages have a root");
+    root.set_kind(kind);
+    for child in children {
+        root.append_child(child);
+    }
+    drop(root);
+    mgr.get_changes(); // apply the changes to the page
+    mgr
+}
+
+pub fn container(kind: UiElementKind, children: Vec<UiElement>) -> UiElement {
+    let mut element = UiElement::new(kind);
+    for child in children {
+        element.append_child(child);
+    }
+    element
+}
+
+pub fn text(content: &str) -> UiElement {
+    UiElement::from_string(content)
+}
+
+pub fn button(id: &str, content: &str) -> UiElement {
+    let mut element = UiElement::new(UiElementKind::Button);
+    element.set_id(id);
+    element.set_text(content);
+    element.set_selectable(true);
+    element
+}
+
+pub fn text_entry(id: &str, label: &str) -> UiElement {
+    let mut element = UiElement::new(UiElementKind::TextEntry);
+    element.set_id(id);
+    element.set_text(label);
+    element.set_selectable(true);
+    element
+}
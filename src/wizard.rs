@@ -0,0 +1,302 @@
+// First run setup, shown in the terminal before the model starts when there is no base to connect to.
+//
+// The steps are: the base's address, the base's id, a test connection, and then this client's id
+// so that it can be approved on the base. Nothing is saved until the last step is accepted.
+
+use std::{fs, path::{Path, PathBuf}};
+
+use crossterm::event::{Event, KeyCode, KeyEventKind, KeyModifiers};
+use spider_client::{SpiderClient, SpiderId2048, Relation, Role, AddressStrategy};
+use tokio::{sync::mpsc::Receiver, time::timeout};
+use tracing::info;
+
+use crate::{
+    config::{SpiderTuiConfig, Profile},
+    connection::CONNECT_TIMEOUT,
+    error::StartupError,
+    keymap::{Action, Keymap, KeyMode, KeyResult},
+    model::processor::line_edit::{self, EditOp},
+    renderer::{Renderer, tui::TUI},
+};
+
+
+
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Step{
+    Address,
+    BaseId,
+    Connecting,
+    ConnectFailed,
+    ShowId,
+}
+
+// What the wizard wants drawn
+pub struct WizardScreen{
+    pub title: String,
+    pub body: String,
+    pub input: Option<(String, usize)>, // text and cursor
+    pub error: Option<String>,
+    pub hint: String,
+}
+
+struct Wizard{
+    step: Step,
+    address: String,
+    address_cursor: usize,
+    base_id: String,
+    base_id_cursor: usize,
+    base: Option<SpiderId2048>, // parsed from base_id once it is accepted
+    error: Option<String>,
+    connected: bool,
+    own_id: String,
+    id_file: String,
+}
+
+// Runs the setup. Returns whether the test connection succeeded, or None if the user gave up.
+pub async fn run(client: &mut SpiderClient, profile: &Profile, config: &SpiderTuiConfig, renderer: &mut TUI, events: &mut Receiver<Event>) -> Result<Option<bool>, StartupError>{
+    let address = profile.addresses.first().cloned().unwrap_or(String::from("localhost:1930"));
+    let mut wizard = Wizard{
+        step: Step::Address,
+        address_cursor: address.chars().count(),
+        address,
+        base_id: String::new(),
+        base_id_cursor: 0,
+        base: None,
+        error: None,
+        connected: false,
+        own_id: serde_json::to_string(&client.self_relation().id).map_err(|e| StartupError::BadFile(PathBuf::from(&profile.state_data_path), e))?,
+        id_file: own_id_path(profile),
+    };
+    let mut keymap = Keymap::from_config(&config.keybindings);
+    renderer.startup();
+
+    loop{
+        renderer.render_wizard(&wizard.screen());
+
+        if wizard.step == Step::Connecting {
+            match timeout(CONNECT_TIMEOUT, client.connect()).await{
+                Ok(_) => {
+                    info!("Test connection to {} succeeded", wizard.address);
+                    wizard.connected = true;
+                    wizard.step = Step::ShowId;
+                    wizard.error = None;
+                },
+                Err(_) => {
+                    info!("Test connection to {} timed out", wizard.address);
+                    wizard.step = Step::ConnectFailed;
+                },
+            }
+            continue;
+        }
+
+        let event = match events.recv().await{
+            Some(event) => event,
+            None => return Ok(None), // terminal input has failed
+        };
+        let key = match event{
+            Event::Key(key) if key.kind != KeyEventKind::Release => key,
+            Event::Paste(text) => {
+                wizard.edit(EditOp::Insert(text));
+                continue;
+            },
+            _ => continue,
+        };
+        if key.modifiers.contains(KeyModifiers::CONTROL) && key.code == KeyCode::Char('c') {
+            return Ok(None);
+        }
+
+        let mode = if wizard.input().is_some() { KeyMode::Edit } else { KeyMode::Page };
+        let action = match keymap.resolve(mode, key){
+            KeyResult::Action(action) => action,
+            KeyResult::Pending => continue,
+            KeyResult::Unbound => {
+                if let KeyCode::Char(ch) = key.code {
+                    if !key.modifiers.intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) {
+                        wizard.typed(ch)?;
+                    }
+                }
+                continue;
+            },
+        };
+        match action{
+            Action::Submit => {
+                if wizard.next(client, profile)? {
+                    return Ok(Some(wizard.connected));
+                }
+            },
+            Action::OpenMenu | Action::Quit => {
+                if !wizard.back() {
+                    return Ok(None);
+                }
+            },
+            Action::CursorLeft => wizard.edit(EditOp::Left),
+            Action::CursorRight => wizard.edit(EditOp::Right),
+            Action::CursorHome => wizard.edit(EditOp::Home),
+            Action::CursorEnd => wizard.edit(EditOp::End),
+            Action::DeleteBack => wizard.edit(EditOp::Backspace),
+            Action::DeleteForward => wizard.edit(EditOp::Delete),
+            Action::DeleteWord => wizard.edit(EditOp::DeleteWord),
+            Action::KillLine => wizard.edit(EditOp::KillLine),
+            _ => {},
+        }
+    }
+}
+
+impl Wizard{
+    fn input(&mut self) -> Option<(&mut String, &mut usize)>{
+        match self.step{
+            Step::Address => Some((&mut self.address, &mut self.address_cursor)),
+            Step::BaseId => Some((&mut self.base_id, &mut self.base_id_cursor)),
+            _ => None,
+        }
+    }
+
+    fn edit(&mut self, op: EditOp){
+        if let Some((text, cursor)) = self.input() {
+            line_edit::apply(text, cursor, op);
+        }
+    }
+
+    // a character that is not bound to anything, typed into the input or used as a choice
+    fn typed(&mut self, ch: char) -> Result<(), StartupError>{
+        match self.step{
+            Step::Address | Step::BaseId => self.edit(EditOp::Insert(String::from(ch))),
+            Step::ConnectFailed if ch == 'c' => {
+                // continue anyway, the base may only accept this client once it is approved
+                self.step = Step::ShowId;
+                self.error = None;
+            },
+            Step::ShowId if ch == 'w' => {
+                fs::write(&self.id_file, &self.own_id)?;
+                self.error = Some(format!("Wrote this client's id to {}", self.id_file));
+            },
+            _ => {},
+        }
+        Ok(())
+    }
+
+    // move to the next step, returns true once setup is finished and saved
+    fn next(&mut self, client: &mut SpiderClient, profile: &Profile) -> Result<bool, StartupError>{
+        match self.step{
+            Step::Address => {
+                self.address = self.address.trim().to_string();
+                if self.address.is_empty() {
+                    self.error = Some(String::from("Enter the address of the base, such as localhost:1930"));
+                }else{
+                    // the address entered replaces any the client had, rather than adding to them
+                    client.clear_strats();
+                    client.add_strat(AddressStrategy::Addr(self.address.clone()));
+                    self.error = None;
+                    self.step = Step::BaseId;
+                }
+            },
+            Step::BaseId => {
+                match parse_base_id(&self.base_id){
+                    Ok(id) => {
+                        // needed in memory for the test connection, saved at the end
+                        client.set_host_relation(Relation { id: id.clone(), role: Role::Peer });
+                        self.base = Some(id);
+                        self.error = None;
+                        self.step = Step::Connecting;
+                    },
+                    Err(e) => self.error = Some(e),
+                }
+            },
+            Step::Connecting => {},
+            Step::ConnectFailed => {
+                self.error = None;
+                self.step = Step::Connecting;
+            },
+            Step::ShowId => {
+                if let Some(id) = &self.base {
+                    let data = serde_json::to_string(id).map_err(|e| StartupError::BadFile(PathBuf::from(&profile.keyfile_path), e))?;
+                    fs::write(&profile.keyfile_path, data)?;
+                }
+                client.save();
+                info!("Saved the host relation from the setup");
+                return Ok(true);
+            },
+        }
+        Ok(false)
+    }
+
+    // move to the previous step, returns false when there is nothing before this one
+    fn back(&mut self) -> bool{
+        self.error = None;
+        self.step = match self.step{
+            Step::Address => return false,
+            Step::BaseId => Step::Address,
+            Step::Connecting | Step::ConnectFailed => Step::BaseId,
+            Step::ShowId => Step::BaseId,
+        };
+        true
+    }
+
+    fn screen(&self) -> WizardScreen{
+        let (title, body, input, hint) = match self.step{
+            Step::Address => (
+                "Base address",
+                String::from("This client has not been paired with a base yet.\nEnter the address of the base to connect to."),
+                Some((self.address.clone(), self.address_cursor)),
+                "enter: next  esc: cancel",
+            ),
+            Step::BaseId => (
+                "Base id",
+                String::from("Paste the base's id, or enter the path of a file containing it.\nThe id can be exported from the base's settings."),
+                Some((self.base_id.clone(), self.base_id_cursor)),
+                "enter: connect  esc: back",
+            ),
+            Step::Connecting => (
+                "Testing connection",
+                format!("Connecting to {}...", self.address),
+                None,
+                "",
+            ),
+            Step::ConnectFailed => (
+                "Testing connection",
+                format!("Could not connect to {} within {} seconds.\nThe base may need to approve this client first.", self.address, CONNECT_TIMEOUT.as_secs()),
+                None,
+                "enter: retry  c: continue anyway  esc: back",
+            ),
+            Step::ShowId => (
+                "Approve this client",
+                format!("Approve this client on the base using its id:\n\n{}", self.own_id),
+                None,
+                "enter: save and start  w: write id to file  esc: back",
+            ),
+        };
+        WizardScreen{
+            title: format!("Set up ({})", title),
+            body,
+            input,
+            error: self.error.clone(),
+            hint: String::from(hint),
+        }
+    }
+}
+
+// the id is pasted as json, or read from a file
+fn parse_base_id(input: &str) -> Result<SpiderId2048, String>{
+    let input = input.trim();
+    if input.is_empty() {
+        return Err(String::from("Enter the base's id or the path of a file containing it"));
+    }
+    if let Ok(id) = serde_json::from_str(input) {
+        return Ok(id);
+    }
+    match fs::read_to_string(input){
+        Ok(data) => serde_json::from_str(&data).map_err(|_| format!("{} does not contain a base id", input)),
+        Err(_) => Err(String::from("That is not a base id, and there is no file with that name")),
+    }
+}
+
+// this client's id is written beside the base's key file
+fn own_id_path(profile: &Profile) -> String{
+    let keyfile = Path::new(&profile.keyfile_path);
+    let path = match keyfile.parent(){
+        Some(parent) => parent.join("spider_tui_id.json"),
+        None => Path::new("spider_tui_id.json").to_path_buf(),
+    };
+    path.to_string_lossy().to_string()
+}